@@ -0,0 +1,175 @@
+/*
+ * Copyright (c) 2026 Na-x4
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+#ifndef AQUESTALK_PROXY_H
+#define AQUESTALK_PROXY_H
+
+/* This file is generated by cbindgen. Do not edit it manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * 関数の結果 (`TimeoutError` と `InvalidSpeedError` 以外は `ResponsePayload` の種類に対応する)
+ */
+typedef enum AqtkProxyStatus {
+  AQTK_PROXY_STATUS_OK = 0,
+  /**
+   * AquesTalk ライブラリ内エラー、または音声記号列が正しくない
+   */
+  AQTK_PROXY_STATUS_AQUESTALK_ERROR = 1,
+  AQTK_PROXY_STATUS_JSON_ERROR = 2,
+  AQTK_PROXY_STATUS_IO_ERROR = 3,
+  /**
+   * 応答を待ちきれなかった (`ClientError::Timeout`)
+   */
+  AQTK_PROXY_STATUS_TIMEOUT_ERROR = 4,
+  /**
+   * 引数が NULL または UTF-8 ではない
+   */
+  AQTK_PROXY_STATUS_INVALID_ARGUMENT = 5,
+  /**
+   * ライブラリ内でパニックが起きた
+   */
+  AQTK_PROXY_STATUS_PANIC = 6,
+  /**
+   * 発話速度が範囲外
+   */
+  AQTK_PROXY_STATUS_INVALID_SPEED_ERROR = 7,
+} AqtkProxyStatus;
+
+/**
+ * クライアントのハンドル
+ *
+ * 複数のスレッドから同時に使用できる。
+ */
+typedef struct AqtkProxyClient AqtkProxyClient;
+
+/**
+ * エラーの詳細
+ *
+ * `message` は `aqtk_proxy_error_free` で解放する。
+ */
+typedef struct AqtkProxyError {
+  /**
+   * AquesTalk のエラーコード (無い場合は 0)
+   */
+  int32_t code;
+  /**
+   * エラーメッセージ (UTF-8)
+   */
+  char *message;
+} AqtkProxyError;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * TCP モードのデーモンに接続するクライアントを作る
+ *
+ * `addr` は `"localhost:21569"` のようなアドレスとポート番号。
+ * `timeout_ms` は接続、送信、受信それぞれの待ち時間の上限 (0 の場合は無制限)。
+ * アドレスの解決と接続は最初の `aqtk_proxy_synthe` で行う。
+ * `addr` が NULL または UTF-8 ではない場合は NULL を返す。
+ *
+ * # Safety
+ *
+ * `addr` は NUL 終端された文字列でなければならない。
+ */
+struct AqtkProxyClient *aqtk_proxy_tcp_client_new(const char *addr,
+                                                  uint64_t timeout_ms);
+
+/**
+ * 標準入出力モードのデーモンを子プロセスとして起動するクライアントを作る
+ *
+ * `args` は `program` に渡す `nargs` 個の引数 (`nargs` が 0 の場合は NULL 可)。
+ * `timeout_ms` は 1 回の要求で応答を待つ時間の上限 (0 の場合は無制限)。
+ * 子プロセスは最初の `aqtk_proxy_synthe` で起動し、終了した場合は次の要求で起動し直す。
+ * 引数が不正な場合は NULL を返す。
+ *
+ * # Safety
+ *
+ * `program` と `args` の各要素は NUL 終端された文字列でなければならない。
+ */
+struct AqtkProxyClient *aqtk_proxy_stdio_client_new(const char *program,
+                                                    const char *const *args,
+                                                    size_t nargs,
+                                                    uint64_t timeout_ms);
+
+/**
+ * クライアントを解放する (NULL の場合は何もしない)
+ *
+ * # Safety
+ *
+ * `client` は `aqtk_proxy_*_client_new` が返したもので、解放済みであってはならない。
+ */
+void aqtk_proxy_client_free(struct AqtkProxyClient *client);
+
+/**
+ * 音声記号列を WAV データに変換する
+ *
+ * 成功した場合は `*wav` と `*wav_len` に WAV データを書き込む。
+ * WAV データは `aqtk_proxy_wav_free` で解放する。
+ * 失敗した場合は `error` (NULL 可) に詳細を書き込む。
+ *
+ * # Safety
+ *
+ * `client` は有効なハンドルで、`voice_type` と `koe` は NUL 終端された文字列、
+ * `wav`、`wav_len` は書き込み可能なポインターでなければならない。
+ */
+enum AqtkProxyStatus aqtk_proxy_synthe(const struct AqtkProxyClient *client,
+                                       const char *voice_type,
+                                       const char *koe,
+                                       int32_t speed,
+                                       uint8_t **wav,
+                                       size_t *wav_len,
+                                       struct AqtkProxyError *error);
+
+/**
+ * `aqtk_proxy_synthe` が返した WAV データを解放する (NULL の場合は何もしない)
+ *
+ * # Safety
+ *
+ * `wav` と `wav_len` は `aqtk_proxy_synthe` が返したもので、解放済みであってはならない。
+ */
+void aqtk_proxy_wav_free(uint8_t *wav,
+                         size_t wav_len);
+
+/**
+ * 音声記号列を AquesTalk に渡せるか確かめる
+ *
+ * デーモンに送らずに、空文字列、長すぎるアクセント句、Shift_JIS で表せない文字などを検出する。
+ * 不正な場合は `AQTK_PROXY_STATUS_AQUESTALK_ERROR` を返し、`error` (NULL 可) に詳細を書き込む。
+ *
+ * # Safety
+ *
+ * `koe` は NUL 終端された文字列でなければならない。
+ */
+enum AqtkProxyStatus aqtk_proxy_koe_validate(const char *koe,
+                                             struct AqtkProxyError *error);
+
+/**
+ * `AqtkProxyError` のメッセージを解放する (NULL の場合は何もしない)
+ *
+ * # Safety
+ *
+ * `error` はライブラリが書き込んだもので、解放済みであってはならない。
+ */
+void aqtk_proxy_error_free(struct AqtkProxyError *error);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* AQUESTALK_PROXY_H */
@@ -0,0 +1,454 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! AquesTalk-proxy クライアントの C API
+//!
+//! ヘッダーファイルは `include/aquestalk_proxy.h` にある。
+//! ライブラリ内でパニックが起きた場合は `AQTK_PROXY_STATUS_PANIC` または NULL を返す。
+//! ライブラリが返したハンドル、WAV データ、エラーメッセージは、
+//! それぞれ対応する `aqtk_proxy_*_free` 関数で解放する。
+
+use std::any::Any;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::time::Duration;
+
+use aquestalk_proxy::aquestalk::{AquesTalk, Koe};
+use aquestalk_proxy::messages::ResponsePayload;
+use aquestalk_proxy::{ClientError, StdioClient, TcpClient};
+
+/// 関数の結果 (`TimeoutError` と `InvalidSpeedError` 以外は `ResponsePayload` の種類に対応する)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AqtkProxyStatus {
+    Ok = 0,
+    /// AquesTalk ライブラリ内エラー、または音声記号列が正しくない
+    AquestalkError = 1,
+    JsonError = 2,
+    IoError = 3,
+    /// 応答を待ちきれなかった (`ClientError::Timeout`)
+    TimeoutError = 4,
+    /// 引数が NULL または UTF-8 ではない
+    InvalidArgument = 5,
+    /// ライブラリ内でパニックが起きた
+    Panic = 6,
+    /// 発話速度が範囲外
+    InvalidSpeedError = 7,
+}
+
+/// エラーの詳細
+///
+/// `message` は `aqtk_proxy_error_free` で解放する。
+#[repr(C)]
+pub struct AqtkProxyError {
+    /// AquesTalk のエラーコード (無い場合は 0)
+    pub code: i32,
+    /// エラーメッセージ (UTF-8)
+    pub message: *mut c_char,
+}
+
+/// クライアントのハンドル
+///
+/// 複数のスレッドから同時に使用できる。
+pub struct AqtkProxyClient(Box<dyn AquesTalk<Wav = Vec<u8>> + Send + Sync>);
+
+enum Error {
+    Client(ClientError),
+    InvalidArgument(&'static str),
+    Panic(String),
+}
+
+impl From<ClientError> for Error {
+    fn from(err: ClientError) -> Self {
+        Self::Client(err)
+    }
+}
+
+impl Error {
+    fn into_parts(self) -> (AqtkProxyStatus, i32, String) {
+        match self {
+            Self::Client(ClientError::Timeout { message }) => {
+                (AqtkProxyStatus::TimeoutError, 0, message)
+            }
+            Self::Client(err) => {
+                let invalid_speed = matches!(err, ClientError::InvalidSpeed { .. });
+                match ResponsePayload::from(err) {
+                    ResponsePayload::AquestalkError { code, message } => {
+                        (AqtkProxyStatus::AquestalkError, code.unwrap_or(0), message)
+                    }
+                    // 範囲外の発話速度は `JsonError` と区別する
+                    ResponsePayload::JsonError { message } if invalid_speed => {
+                        (AqtkProxyStatus::InvalidSpeedError, 0, message)
+                    }
+                    ResponsePayload::JsonError { message } => {
+                        (AqtkProxyStatus::JsonError, 0, message)
+                    }
+                    ResponsePayload::IoError { message } => (AqtkProxyStatus::IoError, 0, message),
+                    ResponsePayload::Wav { .. } => unreachable!(),
+                }
+            }
+            Self::InvalidArgument(message) => {
+                (AqtkProxyStatus::InvalidArgument, 0, message.to_string())
+            }
+            Self::Panic(message) => (AqtkProxyStatus::Panic, 0, message),
+        }
+    }
+}
+
+/// `result` を `AqtkProxyStatus` に変換し、エラーの場合は `error` (NULL 可) に詳細を書き込む
+unsafe fn report(result: Result<(), Error>, error: *mut AqtkProxyError) -> AqtkProxyStatus {
+    let Err(err) = result else {
+        return AqtkProxyStatus::Ok;
+    };
+    let (status, code, message) = err.into_parts();
+    if !error.is_null() {
+        let message = CString::new(message.replace('\0', "")).unwrap();
+        error.write(AqtkProxyError {
+            code,
+            message: message.into_raw(),
+        });
+    }
+    status
+}
+
+/// パニックを C 側に伝えないよう、`f` の中で起きたパニックを `Error::Panic` にする
+fn catch_panic<T, F>(f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(Error::Panic(panic_message(payload))))
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "Rust panic".to_string(),
+        },
+    }
+}
+
+unsafe fn to_str<'a>(s: *const c_char, name: &'static str) -> Result<&'a str, Error> {
+    if s.is_null() {
+        return Err(Error::InvalidArgument(name));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| Error::InvalidArgument(name))
+}
+
+fn timeout(timeout_ms: u64) -> Option<Duration> {
+    (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms))
+}
+
+/// TCP モードのデーモンに接続するクライアントを作る
+///
+/// `addr` は `"localhost:21569"` のようなアドレスとポート番号。
+/// `timeout_ms` は接続、送信、受信それぞれの待ち時間の上限 (0 の場合は無制限)。
+/// アドレスの解決と接続は最初の `aqtk_proxy_synthe` で行う。
+/// `addr` が NULL または UTF-8 ではない場合は NULL を返す。
+///
+/// # Safety
+///
+/// `addr` は NUL 終端された文字列でなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn aqtk_proxy_tcp_client_new(
+    addr: *const c_char,
+    timeout_ms: u64,
+) -> *mut AqtkProxyClient {
+    let client = catch_panic(|| {
+        let addr = to_str(addr, "addr")?;
+
+        let mut client = TcpClient::new(addr.to_string());
+        if let Some(timeout) = timeout(timeout_ms) {
+            client = client
+                .with_connect_timeout(timeout)
+                .with_read_timeout(timeout)
+                .with_write_timeout(timeout);
+        }
+        Ok(AqtkProxyClient(Box::new(client)))
+    });
+    client.map_or(ptr::null_mut(), |client| Box::into_raw(Box::new(client)))
+}
+
+/// 標準入出力モードのデーモンを子プロセスとして起動するクライアントを作る
+///
+/// `args` は `program` に渡す `nargs` 個の引数 (`nargs` が 0 の場合は NULL 可)。
+/// `timeout_ms` は 1 回の要求で応答を待つ時間の上限 (0 の場合は無制限)。
+/// 子プロセスは最初の `aqtk_proxy_synthe` で起動し、終了した場合は次の要求で起動し直す。
+/// 引数が不正な場合は NULL を返す。
+///
+/// # Safety
+///
+/// `program` と `args` の各要素は NUL 終端された文字列でなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn aqtk_proxy_stdio_client_new(
+    program: *const c_char,
+    args: *const *const c_char,
+    nargs: usize,
+    timeout_ms: u64,
+) -> *mut AqtkProxyClient {
+    let client = catch_panic(|| {
+        let program = to_str(program, "program")?;
+        let args = match nargs {
+            0 => &[],
+            _ if args.is_null() => return Err(Error::InvalidArgument("args")),
+            _ => slice::from_raw_parts(args, nargs),
+        };
+        let args = args
+            .iter()
+            .map(|&arg| to_str(arg, "args").map(str::to_string))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut client = StdioClient::new(program.to_string(), move |command| command.args(&args));
+        if let Some(timeout) = timeout(timeout_ms) {
+            client = client.with_timeout(timeout);
+        }
+        Ok(AqtkProxyClient(Box::new(client)))
+    });
+    client.map_or(ptr::null_mut(), |client| Box::into_raw(Box::new(client)))
+}
+
+/// クライアントを解放する (NULL の場合は何もしない)
+///
+/// # Safety
+///
+/// `client` は `aqtk_proxy_*_client_new` が返したもので、解放済みであってはならない。
+#[no_mangle]
+pub unsafe extern "C" fn aqtk_proxy_client_free(client: *mut AqtkProxyClient) {
+    if !client.is_null() {
+        let _ = catch_panic(|| {
+            drop(Box::from_raw(client));
+            Ok(())
+        });
+    }
+}
+
+/// 音声記号列を WAV データに変換する
+///
+/// 成功した場合は `*wav` と `*wav_len` に WAV データを書き込む。
+/// WAV データは `aqtk_proxy_wav_free` で解放する。
+/// 失敗した場合は `error` (NULL 可) に詳細を書き込む。
+///
+/// # Safety
+///
+/// `client` は有効なハンドルで、`voice_type` と `koe` は NUL 終端された文字列、
+/// `wav`、`wav_len` は書き込み可能なポインターでなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn aqtk_proxy_synthe(
+    client: *const AqtkProxyClient,
+    voice_type: *const c_char,
+    koe: *const c_char,
+    speed: i32,
+    wav: *mut *mut u8,
+    wav_len: *mut usize,
+    error: *mut AqtkProxyError,
+) -> AqtkProxyStatus {
+    let result = catch_panic(|| {
+        if client.is_null() {
+            return Err(Error::InvalidArgument("client"));
+        }
+        if wav.is_null() || wav_len.is_null() {
+            return Err(Error::InvalidArgument("wav"));
+        }
+        let voice_type = to_str(voice_type, "voice_type")?;
+        let koe = to_str(koe, "koe")?;
+
+        let data = (*client).0.synthe(voice_type, koe, speed)?;
+        let data = Box::into_raw(data.into_boxed_slice());
+        wav.write(data as *mut u8);
+        wav_len.write(data.len());
+        Ok(())
+    });
+    report(result, error)
+}
+
+/// `aqtk_proxy_synthe` が返した WAV データを解放する (NULL の場合は何もしない)
+///
+/// # Safety
+///
+/// `wav` と `wav_len` は `aqtk_proxy_synthe` が返したもので、解放済みであってはならない。
+#[no_mangle]
+pub unsafe extern "C" fn aqtk_proxy_wav_free(wav: *mut u8, wav_len: usize) {
+    if !wav.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(wav, wav_len)));
+    }
+}
+
+/// 音声記号列を AquesTalk に渡せるか確かめる
+///
+/// デーモンに送らずに、空文字列、長すぎるアクセント句、Shift_JIS で表せない文字などを検出する。
+/// 不正な場合は `AQTK_PROXY_STATUS_AQUESTALK_ERROR` を返し、`error` (NULL 可) に詳細を書き込む。
+///
+/// # Safety
+///
+/// `koe` は NUL 終端された文字列でなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn aqtk_proxy_koe_validate(
+    koe: *const c_char,
+    error: *mut AqtkProxyError,
+) -> AqtkProxyStatus {
+    let result = catch_panic(|| {
+        let koe = to_str(koe, "koe")?;
+        koe.parse::<Koe>()
+            .map_err(|err| Error::from(ClientError::from(err)))?;
+        Ok(())
+    });
+    report(result, error)
+}
+
+/// `AqtkProxyError` のメッセージを解放する (NULL の場合は何もしない)
+///
+/// # Safety
+///
+/// `error` はライブラリが書き込んだもので、解放済みであってはならない。
+#[no_mangle]
+pub unsafe extern "C" fn aqtk_proxy_error_free(error: *mut AqtkProxyError) {
+    if error.is_null() || (*error).message.is_null() {
+        return;
+    }
+    drop(CString::from_raw((*error).message));
+    (*error).message = ptr::null_mut();
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::{CStr, CString};
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn koe_validate() {
+        let mut error = AqtkProxyError {
+            code: 0,
+            message: ptr::null_mut(),
+        };
+        unsafe {
+            let koe = CString::new("こんにちわ").unwrap();
+            let status = aqtk_proxy_koe_validate(koe.as_ptr(), &mut error);
+            assert_eq!(status, AqtkProxyStatus::Ok);
+            assert!(error.message.is_null());
+
+            let koe = CString::new("").unwrap();
+            let status = aqtk_proxy_koe_validate(koe.as_ptr(), &mut error);
+            assert_eq!(status, AqtkProxyStatus::AquestalkError);
+            assert_eq!(error.code, 100);
+            assert_eq!(
+                CStr::from_ptr(error.message).to_str().unwrap(),
+                "その他のエラー"
+            );
+            aqtk_proxy_error_free(&mut error);
+            assert!(error.message.is_null());
+
+            let status = aqtk_proxy_koe_validate(ptr::null(), ptr::null_mut());
+            assert_eq!(status, AqtkProxyStatus::InvalidArgument);
+        }
+    }
+
+    #[test]
+    fn panic() {
+        let mut error = AqtkProxyError {
+            code: 0,
+            message: ptr::null_mut(),
+        };
+        unsafe {
+            let status = report(
+                catch_panic(|| -> Result<(), Error> { panic!("x") }),
+                &mut error,
+            );
+            assert_eq!(status, AqtkProxyStatus::Panic);
+            assert_eq!(CStr::from_ptr(error.message).to_str().unwrap(), "x");
+            aqtk_proxy_error_free(&mut error);
+        }
+    }
+
+    #[test]
+    fn header() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/aquestalk_proxy.h"));
+        assert!(
+            generated == include_str!("../include/aquestalk_proxy.h"),
+            "include/aquestalk_proxy.h is outdated. Regenerate it with cbindgen."
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stdio_synthe() {
+        // 要求を読まずに 1 度だけ応答する
+        let program = CString::new("sh").unwrap();
+        let args = [
+            CString::new("-c").unwrap(),
+            CString::new(
+                r#"echo '{"isSuccess":true,"response":{"type":"Wav","wav":"UklGRg=="}}'; sleep 1"#,
+            )
+            .unwrap(),
+        ];
+        let args = args.iter().map(|arg| arg.as_ptr()).collect::<Vec<_>>();
+        let voice_type = CString::new("f1").unwrap();
+        let koe = CString::new("こんにちわ").unwrap();
+
+        unsafe {
+            let client = aqtk_proxy_stdio_client_new(program.as_ptr(), args.as_ptr(), 2, 0);
+            assert!(!client.is_null());
+
+            let mut wav = ptr::null_mut();
+            let mut wav_len = 0;
+            let status = aqtk_proxy_synthe(
+                client,
+                voice_type.as_ptr(),
+                koe.as_ptr(),
+                100,
+                &mut wav,
+                &mut wav_len,
+                ptr::null_mut(),
+            );
+            assert_eq!(status, AqtkProxyStatus::Ok);
+            assert_eq!(slice::from_raw_parts(wav, wav_len), b"RIFF");
+            aqtk_proxy_wav_free(wav, wav_len);
+
+            aqtk_proxy_client_free(client);
+        }
+    }
+
+    #[test]
+    fn tcp_io_error() {
+        let addr = CString::new("127.0.0.1:1").unwrap();
+        let voice_type = CString::new("f1").unwrap();
+        let koe = CString::new("こんにちわ").unwrap();
+        let mut error = AqtkProxyError {
+            code: 0,
+            message: ptr::null_mut(),
+        };
+
+        unsafe {
+            let client = aqtk_proxy_tcp_client_new(addr.as_ptr(), 1000);
+            let mut wav = ptr::null_mut();
+            let mut wav_len = 0;
+            let status = aqtk_proxy_synthe(
+                client,
+                voice_type.as_ptr(),
+                koe.as_ptr(),
+                100,
+                &mut wav,
+                &mut wav_len,
+                &mut error,
+            );
+            assert_eq!(status, AqtkProxyStatus::IoError);
+            assert!(wav.is_null());
+            assert!(!error.message.is_null());
+            aqtk_proxy_error_free(&mut error);
+
+            aqtk_proxy_client_free(client);
+        }
+    }
+}
@@ -22,23 +22,41 @@ use std::path::Path;
 use std::slice;
 use std::str::FromStr;
 
-use aquestalk_proxy::aquestalk::{AquesTalk, Error, Koe};
-use aquestalk_proxy::messages::ResponsePayload;
+use aquestalk_proxy::aquestalk::{AquesTalk, Error, Koe, SynthesisRequest, Voice};
+use aquestalk_proxy::messages::SPEED_RANGE;
+use aquestalk_proxy::ClientError;
 
 mod dll;
 use dll::AquesTalkDllRaw;
 
 mod koe;
 
-fn new_unknown_voice_type_error(voice_type: &str) -> ResponsePayload {
-    ResponsePayload::AquestalkError {
-        code: None,
-        message: format!("不明な声種 ({})", voice_type),
+/// 発話速度が [`SPEED_RANGE`] の範囲外の要求の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpeedPolicy {
+    /// `InvalidSpeed` エラーを返す
+    #[default]
+    Reject,
+    /// 範囲内に丸めて合成する
+    Clamp,
+}
+
+impl FromStr for SpeedPolicy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(Self::Reject),
+            "clamp" => Ok(Self::Clamp),
+            _ => Err(format!("unknown speed policy \"{}\"", s)),
+        }
     }
 }
 
 #[derive(Clone)]
-pub struct AquesTalkDll(HashMap<String, AquesTalkDllRaw>);
+pub struct AquesTalkDll {
+    dlls: HashMap<String, AquesTalkDllRaw>,
+    speed_policy: SpeedPolicy,
+}
 
 impl AquesTalkDll {
     pub fn new<P>(path: &P) -> Result<Self, Box<dyn std::error::Error>>
@@ -55,7 +73,26 @@ impl AquesTalkDll {
                 aqtks.insert(voice_type, AquesTalkDllRaw::new(path.into_os_string())?);
             }
         }
-        Ok(Self(aqtks))
+        Ok(Self {
+            dlls: aqtks,
+            speed_policy: SpeedPolicy::default(),
+        })
+    }
+
+    pub fn with_speed_policy(mut self, speed_policy: SpeedPolicy) -> Self {
+        self.speed_policy = speed_policy;
+        self
+    }
+
+    pub fn voice_types(&self) -> Vec<&str> {
+        let mut voice_types: Vec<&str> = self.dlls.keys().map(String::as_str).collect();
+        voice_types.sort_unstable();
+        voice_types
+    }
+
+    /// `voice_types` と同じ順の声種
+    pub fn voices(&self) -> Vec<Voice> {
+        self.voice_types().into_iter().map(Voice::from).collect()
     }
 
     pub unsafe fn synthe_raw(
@@ -64,7 +101,7 @@ impl AquesTalkDll {
         koe: &CStr,
         speed: i32,
     ) -> Option<Result<Wav, Error>> {
-        let dll = match self.0.get(&voice_type.to_string()) {
+        let dll = match self.dlls.get(&voice_type.to_string()) {
             Some(dll) => dll,
             None => return None,
         };
@@ -85,19 +122,34 @@ impl AquesTalkDll {
 
 impl AquesTalk for AquesTalkDll {
     type Wav = Wav;
-    fn synthe(&self, voice_type: &str, koe: &str, speed: i32) -> Result<Wav, ResponsePayload> {
-        if !self.0.contains_key(voice_type) {
-            return Err(new_unknown_voice_type_error(voice_type));
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Wav, ClientError> {
+        let voice_type = request.voice_type.as_str();
+        if !self.dlls.contains_key(voice_type) {
+            return Err(ClientError::UnknownVoice {
+                voice_type: voice_type.to_string(),
+            });
         }
 
-        let koe = match Koe::from_str(koe) {
+        let speed = match self.speed_policy {
+            _ if SPEED_RANGE.contains(&request.speed) => request.speed,
+            SpeedPolicy::Reject => {
+                return Err(ClientError::InvalidSpeed {
+                    speed: request.speed,
+                })
+            }
+            SpeedPolicy::Clamp => request
+                .speed
+                .clamp(*SPEED_RANGE.start(), *SPEED_RANGE.end()),
+        };
+
+        let koe = match Koe::from_str(&request.koe) {
             Ok(koe) => koe,
-            Err(err) => return Err(ResponsePayload::from(err)),
+            Err(err) => return Err(ClientError::from(err)),
         };
 
         let wav = match unsafe { self.synthe_raw(voice_type, &koe, speed) } {
             Some(Ok(wav)) => wav,
-            Some(Err(err)) => return Err(ResponsePayload::from(err)),
+            Some(Err(err)) => return Err(ClientError::from(err)),
             None => unreachable!(),
         };
 
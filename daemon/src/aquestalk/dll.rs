@@ -19,8 +19,8 @@ use std::ffi::OsStr;
 use std::os::raw::{c_char, c_int, c_uchar};
 use std::sync::Arc;
 
-type SyntheFuncPtr = unsafe extern "stdcall" fn(*const c_char, c_int, *mut c_int) -> *const c_uchar;
-type FreeWaveFuncPtr = unsafe extern "stdcall" fn(*const c_uchar) -> ();
+type SyntheFuncPtr = unsafe extern "system" fn(*const c_char, c_int, *mut c_int) -> *const c_uchar;
+type FreeWaveFuncPtr = unsafe extern "system" fn(*const c_uchar) -> ();
 
 #[derive(Clone, Debug)]
 pub struct AquesTalkDllRaw(Arc<libloading::Library>);
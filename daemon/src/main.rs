@@ -1,4 +1,4 @@
-// AquesTalk-proxy - Copyright (C) 2021-2025 Na-x4
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
 //
 // This file is part of AquesTalk-proxy.
 //
@@ -18,15 +18,20 @@
 use std::process::exit;
 use std::{env, path::PathBuf};
 
+use aquestalk_proxyd::aquestalk::SpeedPolicy;
 use getopts::{Options, ParsingStyle};
 
 mod proxy;
-use proxy::{run_stdio_proxy, run_tcp_proxy};
+use proxy::{
+    run_bouyomi_proxy, run_http_proxy, run_mcp_proxy, run_stdio_proxy, run_tcp_proxy, run_ws_proxy,
+    run_wyoming_proxy,
+};
 
 pub struct GeneralOptions {
     program: String,
     args: Vec<String>,
     lib_path: PathBuf,
+    speed_policy: SpeedPolicy,
 }
 
 fn format_usage(program: &str, opts: Options) -> String {
@@ -42,6 +47,11 @@ OPTIONS:
 
 MODE:
     tcp                 TCP Socket Mode
+    http                HTTP Server Mode
+    ws                  WebSocket Mode
+    bouyomi             BouyomiChan Compatible Mode
+    wyoming             Wyoming Protocol Mode
+    mcp                 MCP Server Mode
     stdio               Standard IO Mode (Default)
 ",
         program,
@@ -60,6 +70,12 @@ fn run() -> i32 {
     let mut opts = Options::new();
     opts.parsing_style(ParsingStyle::StopAtFirstFree);
     opts.optopt("p", "path", "Path to AquesTalk library", "PATH");
+    opts.optopt(
+        "",
+        "speed-policy",
+        "How to handle speed out of 50-300: reject or clamp (Default: reject)",
+        "POLICY",
+    );
     opts.optflag("h", "help", "Print help");
 
     let matches = match opts.parse(&args[1..]) {
@@ -83,6 +99,18 @@ fn run() -> i32 {
         })
         .unwrap();
 
+    let speed_policy = match matches.opt_get_default("speed-policy", SpeedPolicy::Reject) {
+        Ok(speed_policy) => speed_policy,
+        Err(err) => {
+            eprintln!(
+                "{}\nERROR: --speed-policy: {}",
+                format_usage(&program, opts),
+                err
+            );
+            return 1;
+        }
+    };
+
     let (mode, args): (&str, Vec<String>) = if !matches.free.is_empty() {
         (&matches.free[0], matches.free[1..].to_vec())
     } else {
@@ -92,9 +120,15 @@ fn run() -> i32 {
         program,
         args,
         lib_path,
+        speed_policy,
     };
     let exit_code = match mode {
         "tcp" => run_tcp_proxy(options),
+        "http" => run_http_proxy(options),
+        "ws" => run_ws_proxy(options),
+        "bouyomi" => run_bouyomi_proxy(options),
+        "wyoming" => run_wyoming_proxy(options),
+        "mcp" => run_mcp_proxy(options),
         "stdio" => run_stdio_proxy(options),
         _ => {
             eprintln!(
@@ -1,4 +1,4 @@
-// AquesTalk-proxy - Copyright (C) 2021-2025 Na-x4
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
 //
 // This file is part of AquesTalk-proxy.
 //
@@ -17,29 +17,56 @@
 
 use std::io::{Read, Write};
 
-use aquestalk_proxy::aquestalk::AquesTalk;
+use aquestalk_proxy::aquestalk::{AquesTalk, Voice};
 use optional_take::io::Takable;
-use serde_json::{Deserializer, Value};
+use serde_json::{json, Deserializer, Value};
 
 use aquestalk_proxy::messages::{
     Request, Response, ResponsePayload,
     ResponseStatus::{self, *},
 };
 
+mod bouyomi;
+pub use bouyomi::run_bouyomi_proxy;
+
+mod http;
+pub use http::run_http_proxy;
+
+mod mcp;
+pub use mcp::run_mcp_proxy;
+
+mod shutdown;
+
 mod stdio;
 pub use stdio::run_stdio_proxy;
 
 mod tcp;
 pub use tcp::run_tcp_proxy;
 
+mod ws;
+pub use ws::run_ws_proxy;
+
+mod wyoming;
+pub use wyoming::run_wyoming_proxy;
+
 fn new_limit_reached_error() -> ResponsePayload {
     ResponsePayload::IoError {
         message: "Request is too long".to_string(),
     }
 }
 
+/// 声種の一覧で返す声種の情報
+fn voice_info(voice: &Voice) -> Value {
+    json!({
+        "type": voice.as_str(),
+        "name": voice.display_name(),
+        "gender": voice.gender(),
+        "description": voice.description(),
+    })
+}
+
 fn write_response<W>(
-    mut writer: W,
+    writer: W,
     status: ResponseStatus,
     payload: ResponsePayload,
     request: Option<Value>,
@@ -47,12 +74,60 @@ fn write_response<W>(
 where
     W: Write,
 {
-    serde_json::to_writer(&mut writer, &Response::new(status, payload, request))?;
+    write_message(writer, &Response::new(status, payload, request))
+}
+
+fn write_message<W>(mut writer: W, response: &Response) -> Result<(), Box<dyn std::error::Error>>
+where
+    W: Write,
+{
+    serde_json::to_writer(&mut writer, response)?;
     writer.write(b"\n")?;
     writer.flush()?;
     Ok(())
 }
 
+trait ResponseWriter {
+    fn write_response(
+        &mut self,
+        status: ResponseStatus,
+        payload: ResponsePayload,
+        request: Option<Value>,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    fn write_wav(
+        &mut self,
+        wav: &[u8],
+        request: Option<Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_response(Success, ResponsePayload::from(wav), request)
+    }
+
+    /// 受け取った要求の処理を始める。以降の要求を処理しない場合は `false` を返す。
+    fn begin(&mut self) -> bool {
+        true
+    }
+
+    /// `willClose` を送信済みで、以降の要求を受け付けないか
+    fn is_closed(&self) -> bool {
+        false
+    }
+}
+
+impl<W> ResponseWriter for W
+where
+    W: Write,
+{
+    fn write_response(
+        &mut self,
+        status: ResponseStatus,
+        payload: ResponsePayload,
+        request: Option<Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        write_response(self, status, payload, request)
+    }
+}
+
 fn proxy<R, W, A>(
     reader: R,
     mut writer: W,
@@ -61,7 +136,7 @@ fn proxy<R, W, A>(
 ) -> Result<(), Box<dyn std::error::Error>>
 where
     R: Read,
-    W: Write,
+    W: ResponseWriter,
     A: AquesTalk,
 {
     let mut reader = reader.take_optional(limit);
@@ -77,27 +152,36 @@ where
                     ResponsePayload::from(err)
                 };
 
-                write_response(&mut writer, Error, payload, None)?;
+                writer.write_response(Error, payload, None)?;
                 break;
             }
         };
 
-        let write_response = {
-            let request = request.clone();
-            |status, payload| write_response(&mut writer, status, payload, Some(request))
-        };
+        if !writer.begin() {
+            break;
+        }
 
-        let request: Request = match serde_json::from_value(request) {
-            Ok(request) => request,
+        let parsed: Request = match serde_json::from_value(request.clone()) {
+            Ok(parsed) => parsed,
             Err(err) => {
-                write_response(RecoverableError, ResponsePayload::from(err))?;
+                writer.write_response(
+                    RecoverableError,
+                    ResponsePayload::from(err),
+                    Some(request),
+                )?;
+                if writer.is_closed() {
+                    break;
+                }
                 continue;
             }
         };
 
-        match aqtk.synthe(&request.voice_type, &request.koe, request.speed) {
-            Err(err) => write_response(RecoverableError, err)?,
-            Ok(wav) => write_response(Success, ResponsePayload::from(wav.as_ref()))?,
+        match aqtk.synthe_request(&parsed.into()) {
+            Err(err) => writer.write_response(RecoverableError, err.into(), Some(request))?,
+            Ok(wav) => writer.write_wav(wav.as_ref(), Some(request))?,
+        }
+        if writer.is_closed() {
+            break;
         }
     }
 
@@ -108,7 +192,7 @@ where
 mod test {
     use std::str;
 
-    use aquestalk_proxyd::aquestalk::AquesTalkDll;
+    use aquestalk_proxyd::aquestalk::{AquesTalkDll, SpeedPolicy};
     use serde_json::{json, Value};
 
     use super::proxy;
@@ -141,6 +225,39 @@ mod test {
         );
     }
 
+    #[test]
+    #[cfg_attr(not(all(windows, target_arch = "x86")), ignore)]
+    fn test_invalid_speed() {
+        let aqtk = AquesTalkDll::new(&PATH).unwrap();
+        let input = "{\"koe\":\"あ\",\"speed\":400}".as_bytes();
+        let mut output = Vec::new();
+
+        proxy(input, &mut output, aqtk, None).unwrap();
+        let response: Value = serde_json::from_str(str::from_utf8(&output).unwrap()).unwrap();
+
+        assert_eq!(
+            response,
+            json!(
+                {
+                    "isSuccess": false,
+                    "response": {
+                        "type": "JsonError",
+                        "message": "範囲外の発話速度 (400)"
+                    },
+                    "request": { "koe": "あ", "speed": 400 }
+                }
+            )
+        );
+
+        let aqtk = AquesTalkDll::new(&PATH)
+            .unwrap()
+            .with_speed_policy(SpeedPolicy::Clamp);
+        let mut output = Vec::new();
+        proxy(input, &mut output, aqtk, None).unwrap();
+        let response: Value = serde_json::from_str(str::from_utf8(&output).unwrap()).unwrap();
+        assert_eq!(response["isSuccess"], true);
+    }
+
     #[test]
     #[cfg_attr(not(all(windows, target_arch = "x86")), ignore)]
     fn test_reach_limit() {
@@ -0,0 +1,431 @@
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use aquestalk_proxy::aquestalk::AquesTalk;
+use aquestalk_proxyd::aquestalk::{AquesTalkDll, SpeedPolicy};
+use encoding_rs::{SHIFT_JIS, UTF_16LE, UTF_8};
+use getopts::Options;
+use threadpool::ThreadPool;
+
+use crate::GeneralOptions;
+
+const COMMAND_TALK: i16 = 0x0001;
+const COMMAND_PAUSE: i16 = 0x0010;
+const COMMAND_RESUME: i16 = 0x0020;
+const COMMAND_SKIP: i16 = 0x0030;
+const COMMAND_CLEAR: i16 = 0x0040;
+const COMMAND_GET_PAUSE: i16 = 0x0110;
+const COMMAND_GET_NOW_PLAYING: i16 = 0x0120;
+const COMMAND_GET_TASK_COUNT: i16 = 0x0130;
+
+/// 棒読みちゃんの声質番号 1-8 に対応する声種
+const VOICE_TYPES: [&str; 8] = ["f1", "f2", "m1", "m2", "imd1", "r1", "dvd", "jgr"];
+
+struct BouyomiProxyOptions {
+    lib_path: PathBuf,
+    speed_policy: SpeedPolicy,
+    addrs: Vec<String>,
+    num_threads: usize,
+    timeout: Option<Duration>,
+    limit: Option<u64>,
+    voice_type: String,
+    sink: Sink,
+}
+
+fn format_usage(program: &str, opts: Options) -> String {
+    format!(
+        "\
+AquesTalk-proxy BouyomiChan Compatible Mode
+
+USAGE:
+    {} bouyomi [OPTIONS]
+
+OPTIONS:
+{}
+",
+        program,
+        opts.usage_with_format(|opts| { opts.collect::<Vec<String>>().join("\n") })
+    )
+}
+
+fn parse_options(
+    GeneralOptions {
+        program,
+        args,
+        lib_path,
+        speed_policy,
+    }: GeneralOptions,
+) -> Result<BouyomiProxyOptions, i32> {
+    let mut opts = Options::new();
+    opts.optmulti(
+        "l",
+        "listen",
+        "Address and port to listen on (multiple allowed)",
+        "ADDR",
+    );
+    opts.optopt(
+        "n",
+        "threads",
+        "Number of threads for handling requests",
+        "NUM",
+    );
+    opts.optopt(
+        "",
+        "timeout",
+        "Connection timeout in milliseconds",
+        "MILLIS",
+    );
+    opts.optopt("", "limit", "Max message size", "BYTES");
+    opts.optopt("", "voice", "Voice type for the default voice (0)", "TYPE");
+    opts.optopt(
+        "o",
+        "output",
+        "WAV file to write (\"{}\" is replaced with a sequence number)",
+        "PATH",
+    );
+    opts.optopt("", "exec", "Command to pipe WAV data to", "COMMAND");
+    opts.optflag("h", "help", "Print help");
+
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("{}\nERROR: {}", format_usage(&program, opts), f);
+            return Err(1);
+        }
+    };
+
+    if matches.opt_present("h") {
+        println!("{}", format_usage(&program, opts));
+        return Err(0);
+    }
+
+    let addrs = matches.opt_strs("l");
+    let addrs = if !addrs.is_empty() {
+        addrs
+    } else {
+        vec!["127.0.0.1:50001".into()]
+    };
+    let num_threads = matches.opt_get_default("n", 1).unwrap();
+    let timeout = matches
+        .opt_get("timeout")
+        .unwrap()
+        .map(Duration::from_millis);
+    let limit = matches.opt_get("limit").unwrap();
+    let voice_type = matches.opt_get_default("voice", "f1".to_string()).unwrap();
+    let sink = match (matches.opt_str("output"), matches.opt_str("exec")) {
+        // 複数のスレッドが同じファイルに書き込まないよう、連番の置き換えを必須にする
+        (Some(path), None) if num_threads > 1 && !path.contains("{}") => {
+            eprintln!(
+                "{}\nERROR: --output must contain \"{{}}\" when --threads is greater than 1",
+                format_usage(&program, opts)
+            );
+            return Err(1);
+        }
+        (Some(path), None) => Sink::File(path),
+        (None, Some(command)) => Sink::Command(command),
+        _ => {
+            eprintln!(
+                "{}\nERROR: Either --output or --exec must be specified",
+                format_usage(&program, opts)
+            );
+            return Err(1);
+        }
+    };
+
+    Ok(BouyomiProxyOptions {
+        lib_path,
+        speed_policy,
+        addrs,
+        num_threads,
+        timeout,
+        limit,
+        voice_type,
+        sink,
+    })
+}
+
+enum Sink {
+    File(String),
+    Command(String),
+}
+
+impl Sink {
+    fn write(&self, wav: &[u8], seq: u64) -> io::Result<()> {
+        match self {
+            Sink::File(path) => fs::write(path.replace("{}", &seq.to_string()), wav),
+            Sink::Command(command) => {
+                let mut child = if cfg!(windows) {
+                    Command::new("cmd")
+                        .arg("/C")
+                        .arg(command)
+                        .stdin(Stdio::piped())
+                        .spawn()?
+                } else {
+                    Command::new("sh")
+                        .arg("-c")
+                        .arg(command)
+                        .stdin(Stdio::piped())
+                        .spawn()?
+                };
+                // 書き込みに失敗しても子プロセスを回収する
+                let written = child.stdin.take().unwrap().write_all(wav);
+                let status = child.wait()?;
+                written?;
+                if !status.success() {
+                    return Err(io::Error::other(format!("\"{}\" {}", command, status)));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Talk {
+    speed: i16,
+    tone: i16,
+    volume: i16,
+    voice: i16,
+    message: String,
+}
+
+impl Talk {
+    fn voice_type<'a>(&self, default: &'a str) -> &'a str {
+        match self.voice {
+            1..=8 => VOICE_TYPES[self.voice as usize - 1],
+            _ => default,
+        }
+    }
+
+    /// 範囲外の速度は `--speed-policy` に従って扱う
+    fn speed(&self) -> i32 {
+        match self.speed {
+            -1 => 100,
+            speed => speed as i32,
+        }
+    }
+}
+
+fn read_i16<R: Read>(reader: &mut R) -> io::Result<i16> {
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(i16::from_le_bytes(buf))
+}
+
+fn read_talk<R: Read>(reader: &mut R, limit: Option<u64>) -> io::Result<Talk> {
+    let speed = read_i16(reader)?;
+    let tone = read_i16(reader)?;
+    let volume = read_i16(reader)?;
+    let voice = read_i16(reader)?;
+
+    let mut encoding = [0; 1];
+    reader.read_exact(&mut encoding)?;
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as u64;
+    if limit.is_some_and(|limit| length > limit) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Request is too long",
+        ));
+    }
+
+    let mut message = Vec::new();
+    reader.take(length).read_to_end(&mut message)?;
+    if (message.len() as u64) < length {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+
+    let encoding = match encoding[0] {
+        0 => UTF_8,
+        1 => UTF_16LE,
+        2 => SHIFT_JIS,
+        encoding => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Unknown encoding ({})", encoding),
+            ))
+        }
+    };
+    let (message, _) = encoding.decode_without_bom_handling(&message);
+
+    Ok(Talk {
+        speed,
+        tone,
+        volume,
+        voice,
+        message: message.into_owned(),
+    })
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    aqtk: AquesTalkDll,
+    options: Arc<BouyomiProxyOptions>,
+    seq: Arc<AtomicU64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    stream.set_read_timeout(options.timeout)?;
+    let mut reader = BufReader::new(&stream);
+    let mut writer = &stream;
+
+    loop {
+        let command = match read_i16(&mut reader) {
+            Ok(command) => command,
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        match command {
+            COMMAND_TALK => {
+                let talk = read_talk(&mut reader, options.limit)?;
+                // 棒読みちゃんのプロトコルには応答がないため、合成に失敗しても接続は維持する
+                let wav = match aqtk.synthe(
+                    talk.voice_type(&options.voice_type),
+                    &talk.message,
+                    talk.speed(),
+                ) {
+                    Ok(wav) => wav,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        continue;
+                    }
+                };
+                options
+                    .sink
+                    .write(wav.as_ref(), seq.fetch_add(1, Ordering::Relaxed))?;
+            }
+            // 再生を行わないため、再生の制御は何もしない
+            COMMAND_PAUSE | COMMAND_RESUME | COMMAND_SKIP | COMMAND_CLEAR => (),
+            COMMAND_GET_PAUSE | COMMAND_GET_NOW_PLAYING => writer.write_all(&[0])?,
+            COMMAND_GET_TASK_COUNT => writer.write_all(&0i32.to_le_bytes())?,
+            command => return Err(format!("Unknown command ({:#06x})", command).into()),
+        }
+    }
+
+    stream.shutdown(Shutdown::Write)?;
+    Ok(())
+}
+
+pub fn run_bouyomi_proxy(options: GeneralOptions) -> i32 {
+    let options = match parse_options(options) {
+        Ok(options) => Arc::new(options),
+        Err(err) => return err,
+    };
+
+    let aqtk = AquesTalkDll::new(&options.lib_path)
+        .unwrap()
+        .with_speed_policy(options.speed_policy);
+    let pool = Arc::new(Mutex::new(ThreadPool::new(options.num_threads)));
+    let seq = Arc::new(AtomicU64::new(0));
+
+    (options.addrs)
+        .iter()
+        .map(|addr| {
+            let listener = TcpListener::bind(addr).unwrap();
+            let aqtk = aqtk.clone();
+            let options = Arc::clone(&options);
+            let seq = Arc::clone(&seq);
+            let pool = Arc::clone(&pool);
+
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = stream.unwrap();
+                    let aqtk = aqtk.clone();
+                    let options = Arc::clone(&options);
+                    let seq = Arc::clone(&seq);
+
+                    pool.lock().unwrap().execute(move || {
+                        handle_connection(stream, aqtk, options, seq)
+                            .unwrap_or_else(|err| eprintln!("{}", err));
+                    });
+                }
+            })
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .for_each(|t| t.join().unwrap());
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use encoding_rs::SHIFT_JIS;
+
+    use super::{read_talk, Talk};
+
+    fn talk_packet(speed: i16, voice: i16, encoding: u8, message: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::new();
+        packet.extend_from_slice(&speed.to_le_bytes());
+        packet.extend_from_slice(&(-1i16).to_le_bytes());
+        packet.extend_from_slice(&(-1i16).to_le_bytes());
+        packet.extend_from_slice(&voice.to_le_bytes());
+        packet.push(encoding);
+        packet.extend_from_slice(&(message.len() as u32).to_le_bytes());
+        packet.extend_from_slice(message);
+        packet
+    }
+
+    #[test]
+    fn test_read_talk() {
+        let (message, _, _) = SHIFT_JIS.encode("ゆっくりしていってね");
+        let packet = talk_packet(-1, 3, 2, &message);
+
+        let talk = read_talk(&mut Cursor::new(packet), None).unwrap();
+        assert_eq!(
+            talk,
+            Talk {
+                speed: -1,
+                tone: -1,
+                volume: -1,
+                voice: 3,
+                message: "ゆっくりしていってね".to_string(),
+            }
+        );
+        assert_eq!(talk.voice_type("f1"), "m1");
+        assert_eq!(talk.speed(), 100);
+    }
+
+    #[test]
+    fn test_read_talk_utf16() {
+        let message: Vec<u8> = "あ'".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let packet = talk_packet(400, 0, 1, &message);
+
+        let talk = read_talk(&mut Cursor::new(packet), None).unwrap();
+        assert_eq!(talk.message, "あ'");
+        assert_eq!(talk.voice_type("f2"), "f2");
+        assert_eq!(talk.speed(), 400);
+    }
+
+    #[test]
+    fn test_reach_limit() {
+        let packet = talk_packet(-1, 0, 0, "こんにちわ".as_bytes());
+        assert!(read_talk(&mut Cursor::new(packet), Some(14)).is_err());
+    }
+}
@@ -0,0 +1,674 @@
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use aquestalk_proxy::aquestalk::AquesTalk;
+use aquestalk_proxy::messages::{Request, Response, ResponsePayload, ResponseStatus};
+use aquestalk_proxy::ClientError;
+use aquestalk_proxyd::aquestalk::{AquesTalkDll, SpeedPolicy};
+use getopts::Options;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use threadpool::ThreadPool;
+
+use crate::GeneralOptions;
+
+use super::{new_limit_reached_error, voice_info};
+
+mod openai;
+mod voicevox;
+
+/// Keep-alive の接続を待つ時間のデフォルト
+///
+/// 少ないスレッドを待機中の接続が占有し続けないよう、指定が無くてもタイムアウトさせる。
+const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+const MAX_HEADERS: usize = 32;
+
+struct HttpProxyOptions {
+    lib_path: PathBuf,
+    speed_policy: SpeedPolicy,
+    addrs: Vec<String>,
+    num_threads: usize,
+    timeout: Option<Duration>,
+    limit: Option<u64>,
+    api: ApiOptions,
+}
+
+#[derive(Clone)]
+struct ApiOptions {
+    voicevox: bool,
+    openai: Option<openai::OpenAiOptions>,
+}
+
+fn format_usage(program: &str, opts: Options) -> String {
+    format!(
+        "\
+AquesTalk-proxy HTTP Server Mode
+
+USAGE:
+    {} http [OPTIONS]
+
+OPTIONS:
+{}
+",
+        program,
+        opts.usage_with_format(|opts| { opts.collect::<Vec<String>>().join("\n") })
+    )
+}
+
+fn parse_options(
+    GeneralOptions {
+        program,
+        args,
+        lib_path,
+        speed_policy,
+    }: GeneralOptions,
+) -> Result<HttpProxyOptions, i32> {
+    let mut opts = Options::new();
+    opts.optmulti(
+        "l",
+        "listen",
+        "Address and port to listen on (multiple allowed)",
+        "ADDR",
+    );
+    opts.optopt(
+        "n",
+        "threads",
+        "Number of threads for handling requests",
+        "NUM",
+    );
+    opts.optopt(
+        "",
+        "timeout",
+        "Keep-alive timeout in milliseconds (0 to disable, Default: 5000)",
+        "MILLIS",
+    );
+    opts.optopt("", "limit", "Max request body size", "BYTES");
+    opts.optflag("", "voicevox", "Enable VOICEVOX engine compatible API");
+    opts.optflag("", "openai", "Enable OpenAI compatible speech API");
+    opts.optmulti(
+        "",
+        "openai-voice",
+        "Map an OpenAI voice name to a voice type (multiple allowed)",
+        "NAME=TYPE",
+    );
+    opts.optopt(
+        "",
+        "openai-input",
+        "Treat OpenAI input as \"koe\" or \"kana\" (default: koe)",
+        "FORMAT",
+    );
+    opts.optflag("h", "help", "Print help");
+
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("{}\nERROR: {}", format_usage(&program, opts), f);
+            return Err(1);
+        }
+    };
+
+    if matches.opt_present("h") {
+        println!("{}", format_usage(&program, opts));
+        return Err(0);
+    }
+
+    let addrs = matches.opt_strs("l");
+    let addrs = if !addrs.is_empty() {
+        addrs
+    } else {
+        vec!["127.0.0.1:21570".into(), "[::1]:21570".into()]
+    };
+    let num_threads = matches.opt_get_default("n", 1).unwrap();
+    let timeout = match matches.opt_get("timeout").unwrap() {
+        Some(0) => None,
+        Some(timeout) => Some(Duration::from_millis(timeout)),
+        None => Some(DEFAULT_KEEP_ALIVE_TIMEOUT),
+    };
+    let limit = matches.opt_get("limit").unwrap();
+    let openai = if matches.opt_present("openai") {
+        let options = matches
+            .opt_get_default("openai-input", openai::InputFormat::Koe)
+            .and_then(|input| openai::OpenAiOptions::new(&matches.opt_strs("openai-voice"), input));
+        match options {
+            Ok(options) => Some(options),
+            Err(err) => {
+                eprintln!("{}\nERROR: {}", format_usage(&program, opts), err);
+                return Err(1);
+            }
+        }
+    } else {
+        None
+    };
+    let api = ApiOptions {
+        voicevox: matches.opt_present("voicevox"),
+        openai,
+    };
+
+    Ok(HttpProxyOptions {
+        lib_path,
+        speed_policy,
+        addrs,
+        num_threads,
+        timeout,
+        limit,
+        api,
+    })
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    close: bool,
+    body: Vec<u8>,
+}
+
+struct HttpResponse {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    fn new(status: u16, content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            content_type,
+            body,
+        }
+    }
+
+    fn status(status: u16) -> Self {
+        Self::new(
+            status,
+            "text/plain; charset=utf-8",
+            reason_phrase(status).into(),
+        )
+    }
+
+    fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        Self::new(
+            status,
+            "application/json",
+            serde_json::to_vec(value).unwrap(),
+        )
+    }
+
+    fn error(payload: ResponsePayload, request: Option<Value>) -> Self {
+        Self::json(
+            status_code(&payload),
+            &Response::new(ResponseStatus::RecoverableError, payload, request),
+        )
+    }
+
+    fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W, close: bool) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\n",
+            self.status,
+            reason_phrase(self.status)
+        )?;
+        if self.status != 204 {
+            write!(
+                writer,
+                "Content-Type: {}\r\nContent-Length: {}\r\n",
+                self.content_type,
+                self.body.len()
+            )?;
+        }
+        for (name, value) in &self.headers {
+            write!(writer, "{}: {}\r\n", name, value)?;
+        }
+        if close {
+            writer.write_all(b"Connection: close\r\n")?;
+        }
+        writer.write_all(b"\r\n")?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        422 => "Unprocessable Content",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+fn status_code(payload: &ResponsePayload) -> u16 {
+    match payload {
+        ResponsePayload::Wav { .. } => 200,
+        ResponsePayload::JsonError { .. } => 400,
+        ResponsePayload::AquestalkError {
+            code: None | Some(101 | 103 | 104 | 109 | 110 | 203),
+            ..
+        } => 500,
+        ResponsePayload::AquestalkError { .. } => 422,
+        ResponsePayload::IoError { .. } => 500,
+    }
+}
+
+/// 合成の失敗に対応するステータスコード
+///
+/// 不明な声種は応答の形式では区別できないため、`ClientError` から判断する。
+fn error_status(err: &ClientError) -> u16 {
+    match err {
+        ClientError::UnknownVoice { .. } => 404,
+        err => status_code(&err.clone().into()),
+    }
+}
+
+enum ReadError {
+    Io(io::Error),
+    Http(HttpResponse),
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+fn read_request<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    limit: Option<u64>,
+) -> Result<Option<HttpRequest>, ReadError>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut head = Vec::new();
+    loop {
+        let start = head.len();
+        let read = reader
+            .by_ref()
+            .take((MAX_HEADER_SIZE + 1 - start) as u64)
+            .read_until(b'\n', &mut head)?;
+        if read == 0 {
+            if head.is_empty() {
+                return Ok(None);
+            }
+            return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        if head.len() > MAX_HEADER_SIZE {
+            return Err(ReadError::Http(HttpResponse::status(431)));
+        }
+        if head[start..] == *b"\r\n" || head[start..] == *b"\n" {
+            if start == 0 {
+                head.clear();
+                continue;
+            }
+            break;
+        }
+    }
+
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut parsed = httparse::Request::new(&mut headers);
+    match parsed.parse(&head) {
+        Ok(httparse::Status::Complete(_)) => (),
+        Err(httparse::Error::TooManyHeaders) => {
+            return Err(ReadError::Http(HttpResponse::status(431)))
+        }
+        _ => return Err(ReadError::Http(HttpResponse::status(400))),
+    }
+
+    let (path, query) = match parsed.path.unwrap().split_once('?') {
+        Some((path, query)) => (
+            path,
+            form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect(),
+        ),
+        None => (parsed.path.unwrap(), Vec::new()),
+    };
+
+    let mut close = parsed.version == Some(0);
+    let mut expect_continue = false;
+    let mut content_length = 0;
+    for header in parsed.headers.iter() {
+        let value = match str::from_utf8(header.value) {
+            Ok(value) => value.trim(),
+            Err(_) => return Err(ReadError::Http(HttpResponse::status(400))),
+        };
+        if header.name.eq_ignore_ascii_case("connection") {
+            for token in value.split(',').map(str::trim) {
+                if token.eq_ignore_ascii_case("close") {
+                    close = true;
+                } else if token.eq_ignore_ascii_case("keep-alive") {
+                    close = false;
+                }
+            }
+        } else if header.name.eq_ignore_ascii_case("content-length") {
+            content_length = match value.parse::<u64>() {
+                Ok(length) => length,
+                Err(_) => return Err(ReadError::Http(HttpResponse::status(400))),
+            };
+        } else if header.name.eq_ignore_ascii_case("transfer-encoding") {
+            return Err(ReadError::Http(HttpResponse::status(411)));
+        } else if header.name.eq_ignore_ascii_case("expect") {
+            expect_continue = value.eq_ignore_ascii_case("100-continue");
+        }
+    }
+
+    if limit.is_some_and(|limit| content_length > limit) {
+        let mut response = HttpResponse::error(new_limit_reached_error(), None);
+        response.status = 413;
+        return Err(ReadError::Http(response));
+    }
+
+    if expect_continue && content_length > 0 {
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        writer.flush()?;
+    }
+
+    let mut body = Vec::new();
+    reader.take(content_length).read_to_end(&mut body)?;
+    if (body.len() as u64) < content_length {
+        return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+    }
+
+    Ok(Some(HttpRequest {
+        method: parsed.method.unwrap().to_string(),
+        path: path.to_string(),
+        query,
+        close,
+        body,
+    }))
+}
+
+/// カタカナをひらがなに変換する
+///
+/// ゔ、ゕ、ゖ は Shift_JIS に無く AquesTalk に渡せないため、
+/// ヴ はそのまま (音声記号列として使える)、ヵ と ヶ は か と け にする。
+fn katakana_to_hiragana(c: char) -> char {
+    match c {
+        'ァ'..='ン' => char::from_u32(c as u32 - 0x60).unwrap(),
+        'ヵ' => 'か',
+        'ヶ' => 'け',
+        c => c,
+    }
+}
+
+fn query_to_value(query: &[(String, String)]) -> Value {
+    query
+        .iter()
+        .map(|(key, value)| {
+            let value = match (key.as_str(), value.parse::<i32>()) {
+                ("speed", Ok(speed)) => Value::from(speed),
+                _ => Value::from(value.as_str()),
+            };
+            (key.clone(), value)
+        })
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+fn synthesize<A>(aqtk: &A, request: Value) -> HttpResponse
+where
+    A: AquesTalk,
+{
+    let parsed: Request = match serde_json::from_value(request.clone()) {
+        Ok(parsed) => parsed,
+        Err(err) => return HttpResponse::error(ResponsePayload::from(err), Some(request)),
+    };
+
+    match aqtk.synthe_request(&parsed.into()) {
+        Ok(wav) => HttpResponse::new(200, "audio/wav", wav.as_ref().to_vec()),
+        Err(err) => HttpResponse {
+            status: error_status(&err),
+            ..HttpResponse::error(err.into(), Some(request))
+        },
+    }
+}
+
+fn route(request: &HttpRequest, aqtk: &AquesTalkDll, api: &ApiOptions) -> HttpResponse {
+    if api.voicevox {
+        if let Some(response) = voicevox::route(request, aqtk) {
+            return response;
+        }
+    }
+    if let Some(options) = &api.openai {
+        if let Some(response) = openai::route(request, aqtk, options) {
+            return response;
+        }
+    }
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/synthesize") => match serde_json::from_slice(&request.body) {
+            Ok(body) => synthesize(aqtk, body),
+            Err(err) => HttpResponse::error(ResponsePayload::from(err), None),
+        },
+        ("GET", "/synthesize") => synthesize(aqtk, query_to_value(&request.query)),
+        (_, "/synthesize") => HttpResponse::status(405).header("Allow", "GET, POST"),
+        ("GET", "/voices") => {
+            let voices: Vec<_> = aqtk.voices().iter().map(voice_info).collect();
+            HttpResponse::json(200, &voices)
+        }
+        (_, "/voices") => HttpResponse::status(405).header("Allow", "GET"),
+        _ => HttpResponse::status(404),
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    aqtk: AquesTalkDll,
+    timeout: Option<Duration>,
+    limit: Option<u64>,
+    api: ApiOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    stream.set_read_timeout(timeout)?;
+    let mut reader = BufReader::new(&stream);
+    let mut writer = BufWriter::new(&stream);
+
+    loop {
+        let request = match read_request(&mut reader, &mut writer, limit) {
+            Ok(Some(request)) => request,
+            Ok(None) => break,
+            Err(ReadError::Http(response)) => {
+                response.write_to(&mut writer, true)?;
+                break;
+            }
+            Err(ReadError::Io(err))
+                if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                break
+            }
+            Err(ReadError::Io(err)) => return Err(err.into()),
+        };
+
+        route(&request, &aqtk, &api).write_to(&mut writer, request.close)?;
+        if request.close {
+            break;
+        }
+    }
+
+    stream.shutdown(Shutdown::Write)?;
+    Ok(())
+}
+
+pub fn run_http_proxy(options: GeneralOptions) -> i32 {
+    let options = match parse_options(options) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+
+    let aqtk = AquesTalkDll::new(&options.lib_path)
+        .unwrap()
+        .with_speed_policy(options.speed_policy);
+    let pool = Arc::new(Mutex::new(ThreadPool::new(options.num_threads)));
+
+    (options.addrs)
+        .iter()
+        .map(|addr| {
+            let listener = TcpListener::bind(addr).unwrap();
+            let aqtk = aqtk.clone();
+            let timeout = options.timeout;
+            let limit = options.limit;
+            let api = options.api.clone();
+            let pool = Arc::clone(&pool);
+
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = stream.unwrap();
+                    let aqtk = aqtk.clone();
+                    let api = api.clone();
+
+                    pool.lock().unwrap().execute(move || {
+                        handle_connection(stream, aqtk, timeout, limit, api)
+                            .unwrap_or_else(|err| eprintln!("{}", err));
+                    });
+                }
+            })
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .for_each(|t| t.join().unwrap());
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, BufReader};
+
+    use aquestalk_proxy::aquestalk::Koe;
+    use aquestalk_proxy::messages::ResponsePayload;
+    use aquestalk_proxy::ClientError;
+    use serde_json::json;
+
+    use super::{
+        error_status, katakana_to_hiragana, query_to_value, read_request, status_code, HttpRequest,
+        ReadError,
+    };
+
+    fn read(input: &str, limit: Option<u64>) -> Result<Option<HttpRequest>, ReadError> {
+        read_request(
+            &mut BufReader::new(input.as_bytes()),
+            &mut io::sink(),
+            limit,
+        )
+    }
+
+    #[test]
+    fn test_read_get_request() {
+        let request = read(
+            "GET /synthesize?type=f2&koe=%E3%81%82&speed=120 HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            None,
+        )
+        .ok()
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/synthesize");
+        assert!(!request.close);
+        assert!(request.body.is_empty());
+        assert_eq!(
+            query_to_value(&request.query),
+            json!({ "type": "f2", "koe": "あ", "speed": 120 })
+        );
+    }
+
+    #[test]
+    fn test_read_post_request() {
+        let mut reader = BufReader::new(
+            "POST /synthesize HTTP/1.0\r\nContent-Length: 12\r\n\r\n{\"koe\":\"a\"}\n\r\nGET /voices HTTP/1.1\r\n\r\n"
+                .as_bytes(),
+        );
+
+        let request = read_request(&mut reader, &mut io::sink(), None)
+            .ok()
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.method, "POST");
+        assert!(request.close);
+        assert_eq!(request.body, b"{\"koe\":\"a\"}\n");
+
+        let request = read_request(&mut reader, &mut io::sink(), None)
+            .ok()
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.path, "/voices");
+
+        assert!(read_request(&mut reader, &mut io::sink(), None)
+            .ok()
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_reach_limit() {
+        match read(
+            "POST /synthesize HTTP/1.1\r\nContent-Length: 38\r\n\r\n{\"koe\":\"こんにちわ、せ'かい\"}",
+            Some(37),
+        ) {
+            Err(ReadError::Http(response)) => assert_eq!(response.status, 413),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_status_code() {
+        let unknown_voice = ClientError::UnknownVoice {
+            voice_type: "invalid type".into(),
+        };
+        let invalid_koe = ResponsePayload::from(aquestalk_proxy::aquestalk::Error::new(105));
+        let json_error = ResponsePayload::JsonError { message: "".into() };
+
+        // 不明な声種は声種のエラーから判断し、エラーコードの有無では判断しない
+        assert_eq!(error_status(&unknown_voice), 404);
+        assert_eq!(status_code(&unknown_voice.into()), 500);
+        assert_eq!(status_code(&invalid_koe), 422);
+        assert_eq!(status_code(&json_error), 400);
+    }
+
+    #[test]
+    fn test_katakana_to_hiragana() {
+        let hiragana: String = "ァヴヵヶー".chars().map(katakana_to_hiragana).collect();
+        assert_eq!(hiragana, "ぁヴかけー");
+        assert!("ゔ".parse::<Koe>().is_err());
+        assert!(hiragana.parse::<Koe>().is_ok());
+    }
+}
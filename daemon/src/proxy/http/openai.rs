@@ -0,0 +1,254 @@
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+//! OpenAI 互換の音声合成 API (`/v1/audio/speech`)
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use aquestalk_proxy::aquestalk::AquesTalk;
+use aquestalk_proxy::messages::ResponsePayload;
+use aquestalk_proxy::ClientError;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{error_status, katakana_to_hiragana, HttpRequest, HttpResponse};
+
+/// 声種の割り当てが指定されていない場合の OpenAI の音声名と声種の対応
+const DEFAULT_VOICES: [(&str, &str); 6] = [
+    ("alloy", "f1"),
+    ("echo", "m1"),
+    ("fable", "f2"),
+    ("nova", "r1"),
+    ("onyx", "m2"),
+    ("shimmer", "dvd"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum InputFormat {
+    Koe,
+    Kana,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "koe" => Ok(Self::Koe),
+            "kana" => Ok(Self::Kana),
+            _ => Err(format!("Unknown input format \"{}\"", s)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct OpenAiOptions {
+    voices: HashMap<String, String>,
+    input: InputFormat,
+}
+
+impl OpenAiOptions {
+    /// `NAME=TYPE` 形式で指定された対応を既定の対応に上書きする
+    pub(super) fn new(voices: &[String], input: InputFormat) -> Result<Self, String> {
+        let mut map: HashMap<String, String> = DEFAULT_VOICES
+            .iter()
+            .map(|&(name, voice_type)| (name.to_string(), voice_type.to_string()))
+            .collect();
+        for voice in voices {
+            match voice.split_once('=') {
+                Some((name, voice_type)) if !name.is_empty() && !voice_type.is_empty() => {
+                    map.insert(name.to_string(), voice_type.to_string());
+                }
+                _ => return Err(format!("Invalid voice mapping \"{}\"", voice)),
+            }
+        }
+        Ok(Self { voices: map, input })
+    }
+
+    /// 対応が無い音声名は声種名としてそのまま使用する
+    fn voice_type<'a>(&'a self, voice: &'a str) -> &'a str {
+        self.voices.get(voice).map(String::as_str).unwrap_or(voice)
+    }
+}
+
+#[derive(Deserialize)]
+struct SpeechRequest {
+    input: String,
+    voice: String,
+    #[serde(default = "default_speed")]
+    speed: f64,
+    #[serde(default = "default_response_format")]
+    response_format: String,
+}
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+/// OpenAI と同じく、省略時は `mp3` として扱う
+fn default_response_format() -> String {
+    "mp3".to_string()
+}
+
+/// かな文字列を音声記号列に正規化する
+fn normalize_kana(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '.' | '．' | '!' | '！' => '。',
+            ',' | '，' => '、',
+            '?' => '？',
+            c => katakana_to_hiragana(c),
+        })
+        .collect()
+}
+
+fn speech<A>(
+    request: &HttpRequest,
+    aqtk: &A,
+    options: &OpenAiOptions,
+) -> Result<HttpResponse, ClientError>
+where
+    A: AquesTalk,
+{
+    let request: SpeechRequest = serde_json::from_slice(&request.body)?;
+
+    // AquesTalk が出力できるのは WAV のみ
+    if request.response_format != "wav" {
+        return Err(ClientError::Protocol {
+            message: format!(
+                "unsupported response_format `{}` (only `wav` is supported)",
+                request.response_format
+            ),
+        });
+    }
+
+    let koe = match options.input {
+        InputFormat::Koe => request.input,
+        InputFormat::Kana => normalize_kana(&request.input),
+    };
+    // 範囲外の発話速度は `--speed-policy` に従って扱う
+    let speed = (request.speed * 100.0).round() as i32;
+
+    let wav = aqtk.synthe(options.voice_type(&request.voice), &koe, speed)?;
+    Ok(HttpResponse::new(200, "audio/wav", wav.as_ref().to_vec()))
+}
+
+pub(super) fn route<A>(
+    request: &HttpRequest,
+    aqtk: &A,
+    options: &OpenAiOptions,
+) -> Option<HttpResponse>
+where
+    A: AquesTalk,
+{
+    let response = match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/v1/audio/speech") => speech(request, aqtk, options),
+        (_, "/v1/audio/speech") => Ok(HttpResponse::status(405).header("Allow", "POST")),
+        _ => return None,
+    };
+
+    Some(response.unwrap_or_else(error))
+}
+
+/// OpenAI の API と同じ `{"error": {...}}` 形式のエラーを返す
+fn error(err: ClientError) -> HttpResponse {
+    let status = error_status(&err);
+    let (message, code) = match ResponsePayload::from(err) {
+        ResponsePayload::AquestalkError { code, message } => (message, code.map(|c| c.to_string())),
+        ResponsePayload::JsonError { message } | ResponsePayload::IoError { message } => {
+            (message, None)
+        }
+        ResponsePayload::Wav { .. } => unreachable!(),
+    };
+    let kind = if status < 500 {
+        "invalid_request_error"
+    } else {
+        "server_error"
+    };
+    HttpResponse::json(
+        status,
+        &json!({
+            "error": {
+                "message": message,
+                "type": kind,
+                "param": null,
+                "code": code,
+            }
+        }),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use aquestalk_proxy::ClientError;
+    use serde_json::Value;
+
+    use super::{error, normalize_kana, InputFormat, OpenAiOptions};
+
+    #[test]
+    fn test_normalize_kana() {
+        assert_eq!(
+            normalize_kana("コンニチワ, セ'カイ!"),
+            "こんにちわ、せ'かい。"
+        );
+        assert_eq!(normalize_kana("ゲンキ?"), "げんき？");
+    }
+
+    #[test]
+    fn test_voice_mapping() {
+        let options = OpenAiOptions::new(
+            &["alloy=m2".to_string(), "custom=jgr".to_string()],
+            InputFormat::Koe,
+        )
+        .unwrap();
+        assert_eq!(options.voice_type("alloy"), "m2");
+        assert_eq!(options.voice_type("custom"), "jgr");
+        assert_eq!(options.voice_type("echo"), "m1");
+        assert_eq!(options.voice_type("f2"), "f2");
+
+        assert!(OpenAiOptions::new(&["alloy".to_string()], InputFormat::Koe).is_err());
+        assert!("mp3".parse::<InputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_error() {
+        let response = error(ClientError::AquesTalk {
+            code: Some(105),
+            message: "音声記号列に未定義の読み記号が指定された".to_string(),
+        });
+        assert_eq!(response.status, 422);
+        let body: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+        assert_eq!(body["error"]["code"], "105");
+        assert!(body["error"]["param"].is_null());
+
+        let response = error(ClientError::UnknownVoice {
+            voice_type: "none".to_string(),
+        });
+        assert_eq!(response.status, 404);
+
+        let response = error(ClientError::Transport {
+            message: "broken pipe".to_string(),
+        });
+        assert_eq!(response.status, 500);
+        let body: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(body["error"]["type"], "server_error");
+        assert!(body["error"]["code"].is_null());
+    }
+}
@@ -0,0 +1,273 @@
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+//! VOICEVOX ENGINE 互換 API のサブセット
+//!
+//! 声種は 1 つのスタイルを持つ話者として公開し、スタイル ID は声種名の昇順に割り当てる。
+
+use std::str::FromStr;
+
+use aquestalk_proxy::aquestalk::{fnv1a, AquesTalk, Koe, Voice};
+use aquestalk_proxy::messages::ResponsePayload;
+use aquestalk_proxyd::aquestalk::AquesTalkDll;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{katakana_to_hiragana, HttpRequest, HttpResponse};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct AudioQuery {
+    #[serde(rename = "accent_phrases")]
+    accent_phrases: Vec<AccentPhrase>,
+    speed_scale: f64,
+    pitch_scale: f64,
+    intonation_scale: f64,
+    volume_scale: f64,
+    pre_phoneme_length: f64,
+    post_phoneme_length: f64,
+    output_sampling_rate: u32,
+    output_stereo: bool,
+    kana: Option<String>,
+}
+
+impl Default for AudioQuery {
+    fn default() -> Self {
+        Self {
+            accent_phrases: Vec::new(),
+            speed_scale: 1.0,
+            pitch_scale: 0.0,
+            intonation_scale: 1.0,
+            volume_scale: 1.0,
+            pre_phoneme_length: 0.1,
+            post_phoneme_length: 0.1,
+            output_sampling_rate: 8000,
+            output_stereo: false,
+            kana: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AccentPhrase {
+    moras: Vec<Mora>,
+    accent: usize,
+    #[serde(default)]
+    pause_mora: Option<Mora>,
+    #[serde(default)]
+    is_interrogative: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Mora {
+    text: String,
+}
+
+/// 声種名から決まる固定の UUID
+fn speaker_uuid(voice_type: &str) -> String {
+    let hex = format!("{:032x}", fnv1a(voice_type.as_bytes()));
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// AquesTalk 風記法の読み (カタカナ) を音声記号列 (ひらがな) に変換する
+fn kana_to_koe(kana: &str) -> String {
+    kana.chars()
+        .filter(|&c| c != '_') // 無声化の指定には対応しない
+        .map(katakana_to_hiragana)
+        .collect()
+}
+
+fn accent_phrases_to_koe(accent_phrases: &[AccentPhrase]) -> String {
+    let mut koe = String::new();
+    for (i, accent_phrase) in accent_phrases.iter().enumerate() {
+        for (j, mora) in accent_phrase.moras.iter().enumerate() {
+            koe.push_str(&mora.text);
+            if j + 1 == accent_phrase.accent {
+                koe.push('\'');
+            }
+        }
+        if accent_phrase.is_interrogative {
+            koe.push('？');
+        }
+        if accent_phrase.pause_mora.is_some() {
+            koe.push('、');
+        } else if i + 1 < accent_phrases.len() && !accent_phrase.is_interrogative {
+            koe.push('/');
+        }
+    }
+    kana_to_koe(&koe)
+}
+
+fn speaker<'a>(request: &HttpRequest, voice_types: &[&'a str]) -> Result<&'a str, ResponsePayload> {
+    let speaker = request
+        .query
+        .iter()
+        .find(|(key, _)| key == "speaker")
+        .map(|(_, value)| value)
+        .ok_or_else(|| ResponsePayload::JsonError {
+            message: "missing query parameter `speaker`".to_string(),
+        })?;
+
+    let id = speaker
+        .parse::<usize>()
+        .map_err(|err| ResponsePayload::JsonError {
+            message: format!("invalid query parameter `speaker`: {}", err),
+        })?;
+
+    voice_types
+        .get(id)
+        .copied()
+        .ok_or_else(|| ResponsePayload::JsonError {
+            message: format!("不明な話者 ({})", id),
+        })
+}
+
+fn speakers(voice_types: &[&str]) -> HttpResponse {
+    let speakers: Vec<_> = voice_types
+        .iter()
+        .enumerate()
+        .map(|(id, voice_type)| {
+            json!({
+                "name": Voice::from(*voice_type).display_name(),
+                "speaker_uuid": speaker_uuid(voice_type),
+                "styles": [{ "name": "ノーマル", "id": id, "type": "talk" }],
+                "version": env!("CARGO_PKG_VERSION"),
+                "supported_features": { "permitted_synthesis_morphing": "NOTHING" },
+            })
+        })
+        .collect();
+    HttpResponse::json(200, &speakers)
+}
+
+fn audio_query(
+    request: &HttpRequest,
+    voice_types: &[&str],
+) -> Result<HttpResponse, ResponsePayload> {
+    speaker(request, voice_types)?;
+
+    let text = request
+        .query
+        .iter()
+        .find(|(key, _)| key == "text")
+        .map(|(_, value)| value.clone())
+        .ok_or_else(|| ResponsePayload::JsonError {
+            message: "missing query parameter `text`".to_string(),
+        })?;
+    Koe::from_str(&kana_to_koe(&text))?;
+
+    Ok(HttpResponse::json(
+        200,
+        &AudioQuery {
+            kana: Some(text),
+            ..Default::default()
+        },
+    ))
+}
+
+fn synthesis<A>(
+    request: &HttpRequest,
+    aqtk: &A,
+    voice_types: &[&str],
+) -> Result<HttpResponse, ResponsePayload>
+where
+    A: AquesTalk,
+{
+    let voice_type = speaker(request, voice_types)?;
+    let query: AudioQuery = serde_json::from_slice(&request.body)?;
+
+    let koe = match query.kana {
+        Some(kana) if !kana.is_empty() => kana_to_koe(&kana),
+        _ => accent_phrases_to_koe(&query.accent_phrases),
+    };
+    // 範囲外の発話速度は `--speed-policy` に従って扱う
+    let speed = (query.speed_scale * 100.0).round() as i32;
+
+    let wav = aqtk.synthe(voice_type, &koe, speed)?;
+    Ok(HttpResponse::new(200, "audio/wav", wav.as_ref().to_vec()))
+}
+
+pub(super) fn route(request: &HttpRequest, aqtk: &AquesTalkDll) -> Option<HttpResponse> {
+    let voice_types = aqtk.voice_types();
+    let response = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/version") => Ok(HttpResponse::json(200, &env!("CARGO_PKG_VERSION"))),
+        ("GET", "/speakers") => Ok(speakers(&voice_types)),
+        ("POST", "/audio_query") => audio_query(request, &voice_types),
+        ("POST", "/synthesis") => synthesis(request, aqtk, &voice_types),
+        ("POST", "/initialize_speaker") => {
+            speaker(request, &voice_types).map(|_| HttpResponse::new(204, "text/plain", Vec::new()))
+        }
+        ("GET", "/is_initialized_speaker") => {
+            speaker(request, &voice_types).map(|_| HttpResponse::json(200, &true))
+        }
+        _ => return None,
+    };
+
+    Some(response.unwrap_or_else(|err| match err {
+        // VOICEVOX ENGINE と同じく、不正な引数には 422 を返す
+        ResponsePayload::JsonError { .. } => HttpResponse {
+            status: 422,
+            ..HttpResponse::error(err, None)
+        },
+        err => HttpResponse::error(err, None),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{accent_phrases_to_koe, kana_to_koe, AudioQuery};
+
+    #[test]
+    fn test_kana_to_koe() {
+        assert_eq!(kana_to_koe("コンニチワ'、セ'カイ"), "こんにちわ'、せ'かい");
+        assert_eq!(kana_to_koe("ヴァ_シ'タ？"), "ヴぁし'た？");
+    }
+
+    #[test]
+    fn test_accent_phrases_to_koe() {
+        let query: AudioQuery = serde_json::from_value(json!({
+            "accent_phrases": [
+                {
+                    "moras": [{ "text": "コ" }, { "text": "ン" }, { "text": "ニ" }, { "text": "チ" }, { "text": "ワ" }],
+                    "accent": 5,
+                    "pause_mora": { "text": "、" }
+                },
+                {
+                    "moras": [{ "text": "セ" }, { "text": "カ" }, { "text": "イ" }],
+                    "accent": 1,
+                    "is_interrogative": true
+                }
+            ],
+            "speedScale": 1.5
+        }))
+        .unwrap();
+
+        assert_eq!(query.speed_scale, 1.5);
+        assert_eq!(
+            accent_phrases_to_koe(&query.accent_phrases),
+            "こんにちわ'、せ'かい？"
+        );
+    }
+}
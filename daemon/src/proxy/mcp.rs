@@ -0,0 +1,431 @@
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+//! 標準入出力で動作する MCP (Model Context Protocol) サーバー
+
+use std::fs;
+use std::io::{stdin, stdout, BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+
+use aquestalk_proxy::aquestalk::{AquesTalk, Voice};
+use aquestalk_proxy::messages::SPEED_RANGE;
+use aquestalk_proxyd::aquestalk::{AquesTalkDll, SpeedPolicy};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use getopts::Options;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::GeneralOptions;
+
+use super::voice_info;
+
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+
+struct McpProxyOptions {
+    lib_path: PathBuf,
+    speed_policy: SpeedPolicy,
+    output_dir: Option<PathBuf>,
+}
+
+fn format_usage(program: &str, opts: Options) -> String {
+    format!(
+        "\
+AquesTalk-proxy MCP Server Mode
+
+USAGE:
+    {} mcp [OPTIONS]
+
+OPTIONS:
+{}
+",
+        program,
+        opts.usage_with_format(|opts| { opts.collect::<Vec<String>>().join("\n") })
+    )
+}
+
+fn parse_options(
+    GeneralOptions {
+        program,
+        args,
+        lib_path,
+        speed_policy,
+    }: GeneralOptions,
+) -> Result<McpProxyOptions, i32> {
+    let mut opts = Options::new();
+    opts.optopt(
+        "",
+        "output-dir",
+        "Directory where the synthesize tool may write WAV files",
+        "DIR",
+    );
+    opts.optflag("h", "help", "Print help");
+
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("{}\nERROR: {}", format_usage(&program, opts), f);
+            return Err(1);
+        }
+    };
+
+    if matches.opt_present("h") {
+        println!("{}", format_usage(&program, opts));
+        return Err(0);
+    }
+
+    Ok(McpProxyOptions {
+        lib_path,
+        speed_policy,
+        output_dir: matches.opt_str("output-dir").map(PathBuf::from),
+    })
+}
+
+#[derive(Deserialize)]
+struct JsonRpcRequest {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Deserialize)]
+struct SynthesizeArguments {
+    koe: String,
+    #[serde(default = "default_voice_type")]
+    voice_type: String,
+    #[serde(default = "default_speed")]
+    speed: i32,
+    #[serde(default)]
+    output: Option<PathBuf>,
+}
+
+fn default_voice_type() -> String {
+    "f1".to_string()
+}
+
+fn default_speed() -> i32 {
+    100
+}
+
+fn error(id: Value, code: i32, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() },
+    })
+}
+
+fn tools() -> Value {
+    json!({
+        "tools": [
+            {
+                "name": "synthesize",
+                "description": "AquesTalk で音声記号列 (koe) から音声を合成する。output を指定した場合は出力先ディレクトリ内の WAV ファイルに書き込み、そのパスを返す。",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "koe": { "type": "string", "description": "音声記号列 (例: こんにちわ、せ'かい)" },
+                        "voice_type": { "type": "string", "description": "声種", "default": "f1" },
+                        "speed": { "type": "integer", "description": "発話速度", "minimum": SPEED_RANGE.start(), "maximum": SPEED_RANGE.end(), "default": 100 },
+                        "output": { "type": "string", "description": "WAV ファイルの書き込み先 (出力先ディレクトリからの相対パス)" },
+                    },
+                    "required": ["koe"],
+                },
+            },
+            {
+                "name": "list_voices",
+                "description": "利用可能な声種と、その表示名、性別、説明の一覧を返す。",
+                "inputSchema": { "type": "object", "properties": {} },
+            },
+        ],
+    })
+}
+
+fn tool_error(message: impl Into<String>) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": message.into() }],
+        "isError": true,
+    })
+}
+
+/// `output` を出力先ディレクトリ内のパスに解決する
+///
+/// 絶対パスや `..` を含むパスは出力先ディレクトリの外を指しうるため拒否する。
+fn output_path(output_dir: Option<&Path>, output: &Path) -> Result<PathBuf, String> {
+    let output_dir = output_dir.ok_or("output is disabled (start the server with --output-dir)")?;
+    if !output
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!(
+            "output must be a relative path without \"..\" ({})",
+            output.display()
+        ));
+    }
+    Ok(output_dir.join(output))
+}
+
+fn synthesize<A>(aqtk: &A, arguments: Value, output_dir: Option<&Path>) -> Value
+where
+    A: AquesTalk,
+{
+    let arguments: SynthesizeArguments = match serde_json::from_value(arguments) {
+        Ok(arguments) => arguments,
+        Err(err) => return tool_error(err.to_string()),
+    };
+    let output = match arguments.output {
+        Some(output) => match output_path(output_dir, &output) {
+            Ok(path) => Some(path),
+            Err(err) => return tool_error(err),
+        },
+        None => None,
+    };
+
+    let wav = match aqtk.synthe(&arguments.voice_type, &arguments.koe, arguments.speed) {
+        Ok(wav) => wav,
+        Err(err) => return tool_error(err.to_string()),
+    };
+
+    match output {
+        Some(path) => match fs::write(&path, wav.as_ref()) {
+            Ok(()) => json!({
+                "content": [{ "type": "text", "text": path.display().to_string() }],
+            }),
+            Err(err) => tool_error(err.to_string()),
+        },
+        None => json!({
+            "content": [{
+                "type": "audio",
+                "data": BASE64_STANDARD.encode(wav.as_ref()),
+                "mimeType": "audio/wav",
+            }],
+        }),
+    }
+}
+
+/// 1 行分の JSON-RPC メッセージを処理し、返すべき応答があれば返す
+fn handle_message<A>(
+    message: &str,
+    aqtk: &A,
+    voices: &[Voice],
+    output_dir: Option<&Path>,
+) -> Option<Value>
+where
+    A: AquesTalk,
+{
+    let request: JsonRpcRequest = match serde_json::from_str::<Value>(message) {
+        Ok(value) => match serde_json::from_value(value) {
+            Ok(request) => request,
+            Err(err) => return Some(error(Value::Null, INVALID_REQUEST, err.to_string())),
+        },
+        Err(err) => return Some(error(Value::Null, PARSE_ERROR, err.to_string())),
+    };
+
+    // id の無い通知には応答しない
+    let id = request.id?;
+
+    let result = match request.method.as_str() {
+        "initialize" => {
+            let requested = request.params["protocolVersion"].as_str();
+            let version = PROTOCOL_VERSIONS
+                .into_iter()
+                .find(|&version| Some(version) == requested)
+                .unwrap_or(PROTOCOL_VERSIONS[0]);
+            json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "aquestalk-proxyd", "version": env!("CARGO_PKG_VERSION") },
+            })
+        }
+        "ping" => json!({}),
+        "tools/list" => tools(),
+        "tools/call" => match serde_json::from_value::<ToolCall>(request.params) {
+            Ok(call) if call.name == "synthesize" => synthesize(aqtk, call.arguments, output_dir),
+            Ok(call) if call.name == "list_voices" => {
+                let voices: Vec<_> = voices.iter().map(voice_info).collect();
+                json!({
+                    "content": [{ "type": "text", "text": serde_json::to_string(&voices).unwrap() }],
+                    "structuredContent": { "voices": voices },
+                })
+            }
+            Ok(call) => {
+                return Some(error(
+                    id,
+                    INVALID_PARAMS,
+                    format!("Unknown tool: {}", call.name),
+                ))
+            }
+            Err(err) => return Some(error(id, INVALID_PARAMS, err.to_string())),
+        },
+        method => {
+            return Some(error(
+                id,
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            ))
+        }
+    };
+
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+pub fn run_mcp_proxy(options: GeneralOptions) -> i32 {
+    let options = match parse_options(options) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+
+    let aqtk = AquesTalkDll::new(&options.lib_path)
+        .unwrap()
+        .with_speed_policy(options.speed_policy);
+    let voices = aqtk.voices();
+    let mut stdout = stdout().lock();
+
+    for line in stdin().lock().split(b'\n') {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        };
+        let response = match String::from_utf8(line) {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => handle_message(&line, &aqtk, &voices, options.output_dir.as_deref()),
+            Err(err) => Some(error(Value::Null, PARSE_ERROR, err.to_string())),
+        };
+        if let Some(response) = response {
+            serde_json::to_writer(&mut stdout, &response).unwrap();
+            stdout.write_all(b"\n").unwrap();
+            stdout.flush().unwrap();
+        }
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use aquestalk_proxy::aquestalk::{AquesTalk, SynthesisRequest, Voice};
+    use aquestalk_proxy::ClientError;
+    use serde_json::json;
+
+    use std::path::{Path, PathBuf};
+
+    use super::{handle_message, output_path};
+
+    struct Echo;
+
+    impl AquesTalk for Echo {
+        type Wav = Vec<u8>;
+
+        fn synthe_request(&self, request: &SynthesisRequest) -> Result<Vec<u8>, ClientError> {
+            if request.voice_type != "f1" {
+                return Err(ClientError::UnknownVoice {
+                    voice_type: request.voice_type.clone(),
+                });
+            }
+            Ok(request.koe.as_bytes().to_vec())
+        }
+    }
+
+    fn call(message: &str) -> serde_json::Value {
+        handle_message(
+            message,
+            &Echo,
+            &[Voice::F1, Voice::Custom("yukkuri".to_string())],
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_initialize() {
+        let response = call(
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05"}}"#,
+        );
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+        assert!(handle_message(
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            &Echo,
+            &[],
+            None
+        )
+        .is_none());
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":2,"method":"resources/list"}"#)["error"]["code"],
+            -32601
+        );
+    }
+
+    #[test]
+    fn test_tools_call() {
+        let response = call(
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"synthesize","arguments":{"koe":"あ"}}}"#,
+        );
+        assert_eq!(
+            response["result"],
+            json!({ "content": [{ "type": "audio", "data": "44GC", "mimeType": "audio/wav" }] })
+        );
+
+        let response = call(
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"synthesize","arguments":{"koe":"あ","voice_type":"r1"}}}"#,
+        );
+        assert_eq!(response["result"]["isError"], true);
+
+        let response = call(
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"synthesize","arguments":{"koe":"あ","output":"a.wav"}}}"#,
+        );
+        assert_eq!(response["result"]["isError"], true);
+
+        let response = call(
+            r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"list_voices"}}"#,
+        );
+        assert_eq!(
+            response["result"]["structuredContent"],
+            json!({ "voices": [
+                { "type": "f1", "name": "女性1", "gender": "female", "description": "標準の女性の声 (ゆっくりボイス)" },
+                { "type": "yukkuri", "name": "yukkuri", "gender": null, "description": null },
+            ] })
+        );
+    }
+
+    #[test]
+    fn test_output_path() {
+        let dir = Path::new("out");
+        assert_eq!(
+            output_path(Some(dir), Path::new("a/b.wav")),
+            Ok(PathBuf::from("out/a/b.wav"))
+        );
+        assert!(output_path(None, Path::new("a.wav")).is_err());
+        assert!(output_path(Some(dir), Path::new("../a.wav")).is_err());
+        assert!(output_path(Some(dir), Path::new("/tmp/a.wav")).is_err());
+    }
+}
@@ -0,0 +1,400 @@
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+//! シグナル (Windows ではコンソールの制御イベント) による終了処理
+//!
+//! 終了を要求されると、待機中のセッションには `willClose` の応答を送って閉じ、
+//! 処理中のセッションは応答に `willClose` を付けてから閉じる。
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use aquestalk_proxy::messages::{
+    Response, ResponsePayload,
+    ResponseStatus::{self, *},
+};
+use serde_json::Value;
+
+use super::{write_message, ResponseWriter};
+
+/// 処理中の要求を待つ時間の上限のデフォルト
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 期限までに処理中の要求が終わらなかった場合の終了コード
+pub const EXIT_TIMEOUT: i32 = 2;
+
+/// 終了を要求されたかを確かめる間隔
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+trait Session: Send + Sync {
+    /// 要求を処理中でなければ `willClose` の応答を送って閉じる
+    fn close_if_idle(&self);
+}
+
+#[derive(Default)]
+struct State {
+    requested: bool,
+    busy: usize,
+    closing: usize,
+    next_id: u64,
+    sessions: HashMap<u64, Weak<dyn Session>>,
+}
+
+#[derive(Default)]
+pub struct GracefulShutdown {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl GracefulShutdown {
+    /// シグナルを受け取ったときに終了を要求するようにする
+    pub fn install() -> Result<Arc<Self>, ctrlc::Error> {
+        let shutdown = Arc::new(Self::default());
+        let handler = Arc::clone(&shutdown);
+        ctrlc::set_handler(move || handler.request())?;
+        Ok(shutdown)
+    }
+
+    pub fn request(&self) {
+        self.state.lock().unwrap().requested = true;
+        self.changed.notify_all();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.state.lock().unwrap().requested
+    }
+
+    /// 終了を要求されるまで待つ
+    pub fn wait(&self) {
+        let state = self.state.lock().unwrap();
+        let _state = self
+            .changed
+            .wait_while(state, |state| !state.requested)
+            .unwrap();
+    }
+
+    /// 待機中のセッションを閉じ、処理中の要求が終わるまで `timeout` を上限に待つ
+    ///
+    /// `idle` はセッション以外に残っている処理が無いかを返す。
+    /// 期限までに全て終わった場合は `true` を返す。
+    pub fn drain<F>(self: &Arc<Self>, timeout: Duration, idle: F) -> bool
+    where
+        F: Fn() -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        let sessions: Vec<_> = {
+            let mut state = self.state.lock().unwrap();
+            let sessions: Vec<_> = state.sessions.values().filter_map(Weak::upgrade).collect();
+            state.closing += sessions.len();
+            sessions
+        };
+        // 応答を読まないクライアントへの書き込みで止まらないよう、別のスレッドで閉じる
+        for session in sessions {
+            let shutdown = Arc::clone(self);
+            thread::spawn(move || {
+                session.close_if_idle();
+                shutdown.state.lock().unwrap().closing -= 1;
+                shutdown.changed.notify_all();
+            });
+        }
+
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.busy == 0 && state.closing == 0 && idle() {
+                return true;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            let wait = (deadline - now).min(POLL_INTERVAL);
+            state = self.changed.wait_timeout(state, wait).unwrap().0;
+        }
+    }
+
+    /// `writer` への応答を終了処理の対象にする
+    ///
+    /// `close` は待機中に閉じられたとき、`willClose` の応答を送った後に呼ばれる。
+    /// 既に終了を要求されている場合はすぐに閉じる。
+    pub fn session<W, F>(self: &Arc<Self>, writer: W, close: F) -> SessionWriter<W>
+    where
+        W: Write + Send + 'static,
+        F: Fn() + Send + Sync + 'static,
+    {
+        let inner = Arc::new(SessionInner {
+            state: Mutex::new(SessionState {
+                writer,
+                busy: false,
+                closed: false,
+            }),
+            close: Box::new(close),
+        });
+
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        let weak: Weak<SessionInner<W>> = Arc::downgrade(&inner);
+        state.sessions.insert(id, weak);
+        let requested = state.requested;
+        drop(state);
+
+        if requested {
+            inner.close_if_idle();
+        }
+
+        SessionWriter {
+            inner,
+            shutdown: Arc::clone(self),
+            id,
+        }
+    }
+
+    fn begin_request(&self) {
+        self.state.lock().unwrap().busy += 1;
+    }
+
+    fn end_request(&self) {
+        self.state.lock().unwrap().busy -= 1;
+        self.changed.notify_all();
+    }
+}
+
+struct SessionState<W> {
+    writer: W,
+    busy: bool,
+    closed: bool,
+}
+
+struct SessionInner<W> {
+    state: Mutex<SessionState<W>>,
+    close: Box<dyn Fn() + Send + Sync>,
+}
+
+impl<W> SessionInner<W>
+where
+    W: Write + Send,
+{
+    fn close(&self, state: &mut SessionState<W>) {
+        let response = Response::new(
+            Error,
+            ResponsePayload::IoError {
+                message: "Server is shutting down.".to_string(),
+            },
+            None,
+        );
+        state.closed = true;
+        write_message(&mut state.writer, &response).unwrap_or_else(|err| eprintln!("{}", err));
+        (self.close)();
+    }
+}
+
+impl<W> Session for SessionInner<W>
+where
+    W: Write + Send,
+{
+    fn close_if_idle(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.busy && !state.closed {
+            self.close(&mut state);
+        }
+    }
+}
+
+/// 終了処理の対象になったセッションの応答の書き込み先
+pub struct SessionWriter<W>
+where
+    W: Write + Send + 'static,
+{
+    inner: Arc<SessionInner<W>>,
+    shutdown: Arc<GracefulShutdown>,
+    id: u64,
+}
+
+impl<W> ResponseWriter for SessionWriter<W>
+where
+    W: Write + Send + 'static,
+{
+    fn write_response(
+        &mut self,
+        status: ResponseStatus,
+        payload: ResponsePayload,
+        request: Option<Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed {
+            return Ok(());
+        }
+
+        let mut response = Response::new(status, payload, request);
+        if self.shutdown.is_requested() {
+            response.will_close = Some(true);
+            state.closed = true;
+        }
+        let result = write_message(&mut state.writer, &response);
+
+        if state.busy {
+            state.busy = false;
+            self.shutdown.end_request();
+        }
+        result
+    }
+
+    fn begin(&mut self) -> bool {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed {
+            return false;
+        }
+        if self.shutdown.is_requested() {
+            self.inner.close(&mut state);
+            return false;
+        }
+
+        state.busy = true;
+        self.shutdown.begin_request();
+        true
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.state.lock().unwrap().closed
+    }
+}
+
+impl<W> Drop for SessionWriter<W>
+where
+    W: Write + Send + 'static,
+{
+    fn drop(&mut self) {
+        if self.inner.state.lock().unwrap().busy {
+            self.shutdown.end_request();
+        }
+        self.shutdown
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Write};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use aquestalk_proxy::messages::{Response, ResponsePayload, ResponseStatus::*};
+
+    use super::super::ResponseWriter;
+    use super::GracefulShutdown;
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Output {
+        fn responses(&self) -> Vec<Response> {
+            let output = self.0.lock().unwrap();
+            serde_json::Deserializer::from_slice(&output)
+                .into_iter()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_drain() {
+        let shutdown = Arc::new(GracefulShutdown::default());
+
+        let busy_output = Output::default();
+        let mut busy = shutdown.session(busy_output.clone(), || ());
+        let idle_output = Output::default();
+        let closed = Arc::new(AtomicBool::new(false));
+        let idle = {
+            let closed = Arc::clone(&closed);
+            shutdown.session(idle_output.clone(), move || {
+                closed.store(true, Ordering::SeqCst)
+            })
+        };
+
+        assert!(busy.begin());
+        shutdown.request();
+        assert!(!shutdown.drain(Duration::from_millis(100), || true));
+
+        assert!(idle.is_closed());
+        assert!(closed.load(Ordering::SeqCst));
+        let responses = idle_output.responses();
+        assert_eq!(responses.len(), 1);
+        assert!(!responses[0].is_success);
+        assert_eq!(responses[0].will_close, Some(true));
+
+        let payload = ResponsePayload::from(&b"RIFF"[..]);
+        busy.write_response(Success, payload, None).unwrap();
+        assert!(busy.is_closed());
+        assert!(!busy.begin());
+        assert!(shutdown.drain(Duration::from_secs(1), || true));
+
+        let responses = busy_output.responses();
+        assert_eq!(responses.len(), 1);
+        assert!(responses[0].is_success);
+        assert_eq!(responses[0].will_close, Some(true));
+
+        let late_output = Output::default();
+        let late = shutdown.session(late_output.clone(), || ());
+        assert!(late.is_closed());
+        assert_eq!(late_output.responses()[0].will_close, Some(true));
+    }
+
+    /// 書き込みに時間のかかる出力
+    struct Blocked;
+
+    impl Write for Blocked {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            thread::sleep(Duration::from_secs(2));
+            Err(io::ErrorKind::TimedOut.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_drain_blocked() {
+        let shutdown = Arc::new(GracefulShutdown::default());
+        let _idle = shutdown.session(Blocked, || ());
+
+        // 応答を読まないセッションがあっても期限で戻る
+        shutdown.request();
+        let start = Instant::now();
+        assert!(!shutdown.drain(Duration::from_millis(100), || true));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -1,4 +1,4 @@
-// AquesTalk-proxy - Copyright (C) 2021-2025 Na-x4
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
 //
 // This file is part of AquesTalk-proxy.
 //
@@ -18,17 +18,23 @@
 use std::{
     io::{stdin, stdout},
     path::PathBuf,
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
 };
 
-use aquestalk_proxyd::aquestalk::AquesTalkDll;
+use aquestalk_proxyd::aquestalk::{AquesTalkDll, SpeedPolicy};
 use getopts::Options;
 
 use crate::GeneralOptions;
 
 use super::proxy;
+use super::shutdown::{GracefulShutdown, DEFAULT_TIMEOUT, EXIT_TIMEOUT};
 
 struct StdioProxyOptions {
     lib_path: PathBuf,
+    speed_policy: SpeedPolicy,
+    shutdown_timeout: Duration,
 }
 
 fn format_usage(program: &str, opts: Options) -> String {
@@ -52,9 +58,16 @@ fn parse_options(
         program,
         args,
         lib_path,
+        speed_policy,
     }: GeneralOptions,
 ) -> Result<StdioProxyOptions, i32> {
     let mut opts = Options::new();
+    opts.optopt(
+        "",
+        "shutdown-timeout",
+        "Time to wait for a request in progress on shutdown in milliseconds",
+        "MILLIS",
+    );
     opts.optflag("h", "help", "Print help");
 
     let matches = match opts.parse(args) {
@@ -70,7 +83,16 @@ fn parse_options(
         return Err(0);
     }
 
-    Ok(StdioProxyOptions { lib_path })
+    let shutdown_timeout = matches
+        .opt_get("shutdown-timeout")
+        .unwrap()
+        .map_or(DEFAULT_TIMEOUT, Duration::from_millis);
+
+    Ok(StdioProxyOptions {
+        lib_path,
+        speed_policy,
+        shutdown_timeout,
+    })
 }
 
 pub fn run_stdio_proxy(options: GeneralOptions) -> i32 {
@@ -79,8 +101,33 @@ pub fn run_stdio_proxy(options: GeneralOptions) -> i32 {
         Err(err) => return err,
     };
 
-    let aqtk = AquesTalkDll::new(&options.lib_path).unwrap();
-    proxy(stdin().lock(), stdout().lock(), aqtk, None).unwrap();
+    let aqtk = AquesTalkDll::new(&options.lib_path)
+        .unwrap()
+        .with_speed_policy(options.speed_policy);
+    let shutdown = GracefulShutdown::install().unwrap();
+
+    // 標準入力の EOF とシグナルのどちらでも終了処理に入る
+    let (sender, receiver) = mpsc::channel();
+    let writer = shutdown.session(stdout(), || ());
+    {
+        let shutdown = Arc::clone(&shutdown);
+        thread::spawn(move || {
+            let result = proxy(stdin().lock(), writer, aqtk, None);
+            let _ = sender.send(result.map_err(|err| err.to_string()));
+            shutdown.request();
+        });
+    }
 
-    0
+    shutdown.wait();
+    if !shutdown.drain(options.shutdown_timeout, || true) {
+        eprintln!("Shutdown timed out with a request in progress");
+        return EXIT_TIMEOUT;
+    }
+    match receiver.try_recv() {
+        Ok(Err(err)) => {
+            eprintln!("{}", err);
+            1
+        }
+        _ => 0,
+    }
 }
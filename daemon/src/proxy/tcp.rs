@@ -1,4 +1,4 @@
-// AquesTalk-proxy - Copyright (C) 2021-2025 Na-x4
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
 //
 // This file is part of AquesTalk-proxy.
 //
@@ -15,25 +15,132 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::io::BufWriter;
-use std::net::{Shutdown, TcpListener, TcpStream};
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use aquestalk_proxyd::aquestalk::AquesTalkDll;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(windows)]
+use uds_windows::{UnixListener, UnixStream};
+
+use aquestalk_proxyd::aquestalk::{AquesTalkDll, SpeedPolicy};
 use getopts::Options;
 use threadpool::ThreadPool;
 
 use crate::GeneralOptions;
 
+use super::shutdown::{GracefulShutdown, DEFAULT_TIMEOUT, EXIT_TIMEOUT};
+
 struct TcpProxyOptions {
     lib_path: PathBuf,
-    addrs: Vec<String>,
+    speed_policy: SpeedPolicy,
+    addrs: Vec<ListenAddr>,
     num_threads: usize,
     timeout: Option<Duration>,
     limit: Option<u64>,
+    socket_mode: Option<u32>,
+    shutdown_timeout: Duration,
+}
+
+enum ListenAddr {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl From<String> for ListenAddr {
+    fn from(addr: String) -> Self {
+        match addr.strip_prefix("unix:") {
+            Some(path) => Self::Unix(path.into()),
+            None => Self::Tcp(addr),
+        }
+    }
+}
+
+/// `accept` で待っているリスナーを起こすための接続先
+enum Waker {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl Waker {
+    fn tcp(listener: &TcpListener) -> io::Result<Self> {
+        let mut addr = listener.local_addr()?;
+        // 全てのアドレスで待ち受けている場合はループバックアドレスに接続する
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr.ip() {
+                IpAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                IpAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        Ok(Self::Tcp(addr))
+    }
+
+    /// 一度だけ接続してリスナーの `accept` を返させる
+    fn wake(&self) -> io::Result<()> {
+        match self {
+            Self::Tcp(addr) => TcpStream::connect(addr).map(drop),
+            Self::Unix(path) => UnixStream::connect(path).map(drop),
+        }
+    }
+}
+
+/// 受け付けてから終わるまでの接続の数を数える
+///
+/// プールのキューから取り出されてからセッションを登録するまでの間も数えるため、
+/// 終了処理ではキューの長さではなくこちらを使う。
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn new(count: &Arc<AtomicUsize>) -> Self {
+        count.fetch_add(1, Ordering::SeqCst);
+        Self(Arc::clone(count))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+trait Stream: Read + Write + Send + Sync + Sized + 'static {
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()>;
+    fn try_clone(&self) -> io::Result<Self>;
+    fn shutdown(&self, how: Shutdown) -> io::Result<()>;
+}
+
+impl Stream for TcpStream {
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, dur)
+    }
+
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        TcpStream::shutdown(self, how)
+    }
+}
+
+impl Stream for UnixStream {
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, dur)
+    }
+
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        UnixStream::shutdown(self, how)
+    }
 }
 
 fn format_usage(program: &str, opts: Options) -> String {
@@ -57,13 +164,14 @@ fn parse_options(
         program,
         args,
         lib_path,
+        speed_policy,
     }: GeneralOptions,
 ) -> Result<TcpProxyOptions, i32> {
     let mut opts = Options::new();
     opts.optmulti(
         "l",
         "listen",
-        "Address and port to listen on (multiple allowed)",
+        "Address and port, or unix:PATH to listen on (multiple allowed)",
         "ADDR",
     );
     opts.optopt("n", "threads", "Number of threads for handling requests", "NUM");
@@ -79,6 +187,18 @@ fn parse_options(
         "Max total request size per session",
         "BYTES",
     );
+    opts.optopt(
+        "",
+        "socket-mode",
+        "Permissions of Unix domain sockets in octal",
+        "MODE",
+    );
+    opts.optopt(
+        "",
+        "shutdown-timeout",
+        "Time to wait for requests in progress on shutdown in milliseconds",
+        "MILLIS",
+    );
     opts.optflag("h", "help", "Print help");
 
     let matches = match opts.parse(args) {
@@ -100,37 +220,169 @@ fn parse_options(
     } else {
         vec!["127.0.0.1:21569".into(), "[::1]:21569".into()]
     };
+    let addrs = addrs.into_iter().map(ListenAddr::from).collect();
     let num_threads = matches.opt_get_default("n", 1).unwrap();
     let timeout = matches
         .opt_get("timeout")
         .unwrap()
         .and_then(|t| Some(Duration::from_millis(t)));
     let limit = matches.opt_get("limit").unwrap();
+    let socket_mode = match matches.opt_str("socket-mode") {
+        Some(mode) => match u32::from_str_radix(&mode, 8) {
+            Ok(mode) if cfg!(unix) => Some(mode),
+            Ok(_) => {
+                eprintln!(
+                    "{}\nERROR: --socket-mode is not supported on this platform",
+                    format_usage(&program, opts)
+                );
+                return Err(1);
+            }
+            Err(err) => {
+                eprintln!(
+                    "{}\nERROR: Invalid socket mode \"{}\": {}",
+                    format_usage(&program, opts),
+                    mode,
+                    err
+                );
+                return Err(1);
+            }
+        },
+        None => None,
+    };
+    let shutdown_timeout = matches
+        .opt_get("shutdown-timeout")
+        .unwrap()
+        .map_or(DEFAULT_TIMEOUT, Duration::from_millis);
 
     Ok(TcpProxyOptions {
         lib_path,
+        speed_policy,
         addrs,
         num_threads,
         timeout,
         limit,
+        socket_mode,
+        shutdown_timeout,
     })
 }
 
-fn handle_connection(
-    stream: TcpStream,
+fn handle_connection<S>(
+    stream: S,
     aqtk: AquesTalkDll,
     timeout: Option<Duration>,
     limit: Option<u64>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    shutdown: &Arc<GracefulShutdown>,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: Stream,
+{
     stream.set_read_timeout(timeout)?;
-    super::proxy(
-        stream.try_clone()?,
-        BufWriter::new(stream.try_clone()?),
-        aqtk,
-        limit,
-    )?;
-    stream.shutdown(Shutdown::Write)?;
-    Ok(())
+    let closer = stream.try_clone()?;
+    let writer = shutdown.session(BufWriter::new(stream.try_clone()?), move || {
+        let _ = closer.shutdown(Shutdown::Both);
+    });
+    super::proxy(stream.try_clone()?, writer, aqtk, limit)?;
+    match stream.shutdown(Shutdown::Write) {
+        // 終了処理で既に閉じている
+        Err(err) if err.kind() == ErrorKind::NotConnected => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+fn serve<I, S>(
+    incoming: I,
+    aqtk: AquesTalkDll,
+    timeout: Option<Duration>,
+    limit: Option<u64>,
+    pool: Arc<Mutex<ThreadPool>>,
+    in_flight: Arc<AtomicUsize>,
+    shutdown: Arc<GracefulShutdown>,
+) where
+    I: Iterator<Item = io::Result<S>>,
+    S: Stream,
+{
+    // 終了を要求されると `Waker` の接続で `accept` が返るので、受け付けをやめる
+    for stream in incoming {
+        if shutdown.is_requested() {
+            break;
+        }
+        let stream = stream.unwrap();
+        let aqtk = aqtk.clone();
+        let shutdown = Arc::clone(&shutdown);
+        let in_flight = InFlight::new(&in_flight);
+
+        pool.lock().unwrap().execute(move || {
+            let _in_flight = in_flight;
+            handle_connection(stream, aqtk, timeout, limit, &shutdown)
+                .unwrap_or_else(|err| eprintln!("{}", err));
+        });
+    }
+}
+
+/// `path` が前回起動時に残されたソケットファイルかを返す
+///
+/// ソケット以外のファイルは誤って削除しないよう対象にしない。
+fn is_stale_socket(path: &Path) -> io::Result<bool> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+
+    #[cfg(unix)]
+    let is_socket = {
+        use std::os::unix::fs::FileTypeExt;
+        metadata.file_type().is_socket()
+    };
+    // Windows ではソケットファイルを判別できないため、空のファイルだけを対象にする
+    #[cfg(not(unix))]
+    let is_socket = metadata.is_file() && metadata.len() == 0;
+
+    Ok(is_socket && UnixStream::connect(path).is_err())
+}
+
+fn bind_unix(path: &Path, mode: Option<u32>) -> io::Result<UnixListener> {
+    if is_stale_socket(path)? {
+        fs::remove_file(path)?;
+    }
+
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        return bind_unix_with_mode(path, mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    UnixListener::bind(path)
+}
+
+/// パーミッションを `mode` にしたソケットで待ち受ける
+///
+/// パーミッションを設定するまでの間に他のユーザーから接続されないよう、
+/// 所有者だけが入れるディレクトリで待ち受けを開始してから `path` に移動する。
+#[cfg(unix)]
+fn bind_unix_with_mode(path: &Path, mode: u32) -> io::Result<UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}", std::process::id()));
+    let dir = parent.join(name);
+    let temp = dir.join("socket");
+
+    fs::DirBuilder::new().mode(0o700).create(&dir)?;
+    let result = UnixListener::bind(&temp).and_then(|listener| {
+        fs::set_permissions(&temp, fs::Permissions::from_mode(mode))?;
+        fs::rename(&temp, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&temp);
+    let _ = fs::remove_dir(&dir);
+    result
 }
 
 pub fn run_tcp_proxy(options: GeneralOptions) -> i32 {
@@ -139,35 +391,151 @@ pub fn run_tcp_proxy(options: GeneralOptions) -> i32 {
         Err(err) => return err,
     };
 
-    let aqtk = AquesTalkDll::new(&options.lib_path).unwrap();
+    let aqtk = AquesTalkDll::new(&options.lib_path)
+        .unwrap()
+        .with_speed_policy(options.speed_policy);
     let pool = Arc::new(Mutex::new(ThreadPool::new(options.num_threads)));
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let shutdown = GracefulShutdown::install().unwrap();
 
-    (options.addrs)
+    let threads = (options.addrs)
         .iter()
         .map(|addr| {
-            let listener = TcpListener::bind(addr).unwrap();
             let aqtk = aqtk.clone();
             let timeout = options.timeout;
             let limit = options.limit;
             let pool = Arc::clone(&pool);
+            let in_flight = Arc::clone(&in_flight);
+            let shutdown = Arc::clone(&shutdown);
 
-            thread::spawn(move || {
-                for stream in listener.incoming() {
-                    let stream = stream.unwrap();
-                    let aqtk = aqtk.clone();
-                    let timeout = timeout;
-                    let limit = limit;
-
-                    pool.lock().unwrap().execute(move || {
-                        handle_connection(stream, aqtk, timeout, limit)
-                            .unwrap_or_else(|err| eprintln!("{}", err));
+            match addr {
+                ListenAddr::Tcp(addr) => {
+                    let listener = TcpListener::bind(addr).unwrap();
+                    let waker = Waker::tcp(&listener).unwrap();
+                    let thread = thread::spawn(move || {
+                        serve(
+                            listener.incoming(),
+                            aqtk,
+                            timeout,
+                            limit,
+                            pool,
+                            in_flight,
+                            shutdown,
+                        )
+                    });
+                    (thread, waker)
+                }
+                ListenAddr::Unix(path) => {
+                    let listener = bind_unix(path, options.socket_mode).unwrap();
+                    let waker = Waker::Unix(path.clone());
+                    let thread = thread::spawn(move || {
+                        serve(
+                            listener.incoming(),
+                            aqtk,
+                            timeout,
+                            limit,
+                            pool,
+                            in_flight,
+                            shutdown,
+                        )
                     });
+                    (thread, waker)
                 }
-            })
+            }
         })
-        .collect::<Vec<_>>()
-        .into_iter()
-        .for_each(|t| t.join().unwrap());
+        .collect::<Vec<_>>();
+
+    shutdown.wait();
+    for (thread, waker) in threads {
+        // 起こせなかったリスナーは待たずに残す
+        match waker.wake() {
+            Ok(()) => thread.join().unwrap(),
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+    for addr in &options.addrs {
+        if let ListenAddr::Unix(path) = addr {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    let drained = shutdown.drain(options.shutdown_timeout, || {
+        in_flight.load(Ordering::SeqCst) == 0
+    });
+    if drained {
+        0
+    } else {
+        eprintln!("Shutdown timed out with requests in progress");
+        EXIT_TIMEOUT
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{ListenAddr, Waker};
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bind_unix() {
+        use std::env;
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::net::{UnixListener, UnixStream};
+        use std::process;
 
-    0
+        use super::bind_unix;
+
+        let dir = env::temp_dir().join(format!("aquestalk-proxyd-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("aquestalk.sock");
+
+        // ソケット以外のファイルは削除しない
+        fs::write(&path, "data").unwrap();
+        assert!(bind_unix(&path, None).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"data");
+        fs::remove_file(&path).unwrap();
+
+        // 残っているソケットファイルは削除して待ち受ける
+        drop(UnixListener::bind(&path).unwrap());
+        let listener = bind_unix(&path, Some(0o600)).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        UnixStream::connect(&path).unwrap();
+        listener.accept().unwrap();
+
+        // 作業用のディレクトリは残らない
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        drop(listener);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_waker() {
+        use std::net::TcpListener;
+
+        // 全てのアドレスで待ち受けている場合もループバックアドレスで起こせる
+        let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+        let waker = Waker::tcp(&listener).unwrap();
+        match &waker {
+            Waker::Tcp(addr) => assert!(addr.ip().is_loopback()),
+            Waker::Unix(_) => panic!(),
+        }
+        waker.wake().unwrap();
+        listener.accept().unwrap();
+    }
+
+    #[test]
+    fn test_listen_addr() {
+        match ListenAddr::from("127.0.0.1:21569".to_string()) {
+            ListenAddr::Tcp(addr) => assert_eq!(addr, "127.0.0.1:21569"),
+            ListenAddr::Unix(_) => panic!(),
+        }
+        match ListenAddr::from("unix:/run/aquestalk.sock".to_string()) {
+            ListenAddr::Unix(path) => assert_eq!(path, PathBuf::from("/run/aquestalk.sock")),
+            ListenAddr::Tcp(_) => panic!(),
+        }
+    }
 }
@@ -0,0 +1,451 @@
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::RefCell;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use aquestalk_proxy::messages::{Response, ResponsePayload, ResponseStatus};
+use aquestalk_proxyd::aquestalk::{AquesTalkDll, SpeedPolicy};
+use getopts::Options;
+use serde::Serialize;
+use serde_json::Value;
+use threadpool::ThreadPool;
+use tungstenite::{Message, WebSocket};
+
+use crate::GeneralOptions;
+
+use super::ResponseWriter;
+
+struct WsProxyOptions {
+    lib_path: PathBuf,
+    speed_policy: SpeedPolicy,
+    addrs: Vec<String>,
+    num_threads: usize,
+    timeout: Option<Duration>,
+    limit: Option<u64>,
+    binary: bool,
+}
+
+fn format_usage(program: &str, opts: Options) -> String {
+    format!(
+        "\
+AquesTalk-proxy WebSocket Mode
+
+USAGE:
+    {} ws [OPTIONS]
+
+OPTIONS:
+{}
+",
+        program,
+        opts.usage_with_format(|opts| { opts.collect::<Vec<String>>().join("\n") })
+    )
+}
+
+fn parse_options(
+    GeneralOptions {
+        program,
+        args,
+        lib_path,
+        speed_policy,
+    }: GeneralOptions,
+) -> Result<WsProxyOptions, i32> {
+    let mut opts = Options::new();
+    opts.optmulti(
+        "l",
+        "listen",
+        "Address and port to listen on (multiple allowed)",
+        "ADDR",
+    );
+    opts.optopt(
+        "n",
+        "threads",
+        "Number of threads for handling requests",
+        "NUM",
+    );
+    opts.optopt(
+        "",
+        "timeout",
+        "Connection timeout in milliseconds",
+        "MILLIS",
+    );
+    opts.optopt("", "limit", "Max total request size per session", "BYTES");
+    opts.optflag("", "binary", "Send WAV data as binary frames");
+    opts.optflag("h", "help", "Print help");
+
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("{}\nERROR: {}", format_usage(&program, opts), f);
+            return Err(1);
+        }
+    };
+
+    if matches.opt_present("h") {
+        println!("{}", format_usage(&program, opts));
+        return Err(0);
+    }
+
+    let addrs = matches.opt_strs("l");
+    let addrs = if !addrs.is_empty() {
+        addrs
+    } else {
+        vec!["127.0.0.1:21571".into(), "[::1]:21571".into()]
+    };
+    let num_threads = matches.opt_get_default("n", 1).unwrap();
+    let timeout = matches
+        .opt_get("timeout")
+        .unwrap()
+        .map(Duration::from_millis);
+    let limit = matches.opt_get("limit").unwrap();
+    let binary = matches.opt_present("binary");
+
+    Ok(WsProxyOptions {
+        lib_path,
+        speed_policy,
+        addrs,
+        num_threads,
+        timeout,
+        limit,
+        binary,
+    })
+}
+
+type Socket<S> = Rc<RefCell<WebSocket<S>>>;
+
+struct WsReader<S> {
+    socket: Socket<S>,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<S> WsReader<S> {
+    fn new(socket: Socket<S>) -> Self {
+        Self {
+            socket,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl<S> Read for WsReader<S>
+where
+    S: Read + Write,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            self.buf = match self.socket.borrow_mut().read() {
+                Ok(Message::Text(text)) => text.as_bytes().to_vec(),
+                Ok(Message::Binary(data)) => data.to_vec(),
+                Ok(Message::Close(_)) => return Ok(0),
+                Ok(_) => continue,
+                Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    return Ok(0)
+                }
+                Err(tungstenite::Error::Io(err)) => return Err(err),
+                Err(err) => return Err(io::Error::new(ErrorKind::InvalidData, err)),
+            };
+            self.pos = 0;
+        }
+
+        let len = buf.len().min(self.buf.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// `--binary` で WAV データのバイナリフレームの直前に送るテキストフレーム
+///
+/// `wav` を持たないため `Response` ではなく、`type` が `"WavHeader"` のメッセージとして区別する。
+#[derive(Serialize)]
+struct WavHeader {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// 続くバイナリフレームの長さ (バイト)
+    length: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request: Option<Value>,
+}
+
+impl WavHeader {
+    fn new(wav: &[u8], request: Option<Value>) -> Self {
+        Self {
+            kind: "WavHeader",
+            length: wav.len(),
+            request,
+        }
+    }
+}
+
+struct WsWriter<S> {
+    socket: Socket<S>,
+    binary: bool,
+}
+
+impl<S> ResponseWriter for WsWriter<S>
+where
+    S: Read + Write,
+{
+    fn write_response(
+        &mut self,
+        status: ResponseStatus,
+        payload: ResponsePayload,
+        request: Option<Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = serde_json::to_string(&Response::new(status, payload, request))?;
+        self.socket.borrow_mut().send(Message::text(response))?;
+        Ok(())
+    }
+
+    fn write_wav(
+        &mut self,
+        wav: &[u8],
+        request: Option<Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.binary {
+            return self.write_response(
+                ResponseStatus::Success,
+                ResponsePayload::from(wav),
+                request,
+            );
+        }
+
+        // 要求と対応付けられるよう、`WavHeader` を先に送る
+        let header = serde_json::to_string(&WavHeader::new(wav, request))?;
+        let mut socket = self.socket.borrow_mut();
+        socket.send(Message::text(header))?;
+        socket.send(Message::binary(wav.to_vec()))?;
+        Ok(())
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    aqtk: AquesTalkDll,
+    timeout: Option<Duration>,
+    limit: Option<u64>,
+    binary: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    stream.set_read_timeout(timeout)?;
+    let socket = match tungstenite::accept(stream) {
+        Ok(socket) => Rc::new(RefCell::new(socket)),
+        Err(err) => return Err(err.to_string().into()),
+    };
+
+    super::proxy(
+        WsReader::new(Rc::clone(&socket)),
+        WsWriter {
+            socket: Rc::clone(&socket),
+            binary,
+        },
+        aqtk,
+        limit,
+    )?;
+
+    let mut socket = socket.borrow_mut();
+    match socket.close(None).and_then(|_| socket.flush()) {
+        Ok(()) | Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+            Ok(())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn run_ws_proxy(options: GeneralOptions) -> i32 {
+    let options = match parse_options(options) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+
+    let aqtk = AquesTalkDll::new(&options.lib_path)
+        .unwrap()
+        .with_speed_policy(options.speed_policy);
+    let pool = Arc::new(Mutex::new(ThreadPool::new(options.num_threads)));
+
+    (options.addrs)
+        .iter()
+        .map(|addr| {
+            let listener = TcpListener::bind(addr).unwrap();
+            let aqtk = aqtk.clone();
+            let timeout = options.timeout;
+            let limit = options.limit;
+            let binary = options.binary;
+            let pool = Arc::clone(&pool);
+
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = stream.unwrap();
+                    let aqtk = aqtk.clone();
+
+                    pool.lock().unwrap().execute(move || {
+                        handle_connection(stream, aqtk, timeout, limit, binary)
+                            .unwrap_or_else(|err| eprintln!("{}", err));
+                    });
+                }
+            })
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .for_each(|t| t.join().unwrap());
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::io::{Cursor, Read, Write};
+    use std::rc::Rc;
+
+    use serde_json::{json, Value};
+    use tungstenite::protocol::Role;
+    use tungstenite::{Message, WebSocket};
+
+    use super::{ResponseWriter, WsReader, WsWriter};
+
+    /// 受信したフレームと送信したフレームを別々のバッファで保持するストリーム
+    struct MockStream {
+        input: Cursor<Vec<u8>>,
+        output: Rc<RefCell<Vec<u8>>>,
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn client_frames(messages: Vec<Message>) -> Vec<u8> {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let stream = MockStream {
+            input: Cursor::new(Vec::new()),
+            output: Rc::clone(&output),
+        };
+        let mut client = WebSocket::from_raw_socket(stream, Role::Client, None);
+        for message in messages {
+            client.send(message).unwrap();
+        }
+        let frames = output.borrow().clone();
+        frames
+    }
+
+    fn server_messages(frames: Vec<u8>) -> Vec<Message> {
+        let stream = MockStream {
+            input: Cursor::new(frames),
+            output: Rc::new(RefCell::new(Vec::new())),
+        };
+        let mut client = WebSocket::from_raw_socket(stream, Role::Client, None);
+        let mut messages = Vec::new();
+        while let Ok(message) = client.read() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    #[test]
+    fn test_reader_concatenates_frames() {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let stream = MockStream {
+            input: Cursor::new(client_frames(vec![
+                Message::text("{\"koe\":"),
+                Message::Ping(Vec::new().into()),
+                Message::text("\"あ\"}{\"koe\":\"い\"}"),
+                Message::Close(None),
+            ])),
+            output: Rc::clone(&output),
+        };
+        let socket = Rc::new(RefCell::new(WebSocket::from_raw_socket(
+            stream,
+            Role::Server,
+            None,
+        )));
+
+        let mut text = String::new();
+        WsReader::new(socket).read_to_string(&mut text).unwrap();
+        assert_eq!(text, "{\"koe\":\"あ\"}{\"koe\":\"い\"}");
+    }
+
+    #[test]
+    fn test_writer_binary() {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let stream = MockStream {
+            input: Cursor::new(Vec::new()),
+            output: Rc::clone(&output),
+        };
+        let socket = Rc::new(RefCell::new(WebSocket::from_raw_socket(
+            stream,
+            Role::Server,
+            None,
+        )));
+
+        let mut writer = WsWriter {
+            socket: Rc::clone(&socket),
+            binary: true,
+        };
+        writer
+            .write_wav(b"RIFF", Some(json!({ "koe": "あ" })))
+            .unwrap();
+        writer.binary = false;
+        writer
+            .write_wav(b"RIFF", Some(json!({ "koe": "あ" })))
+            .unwrap();
+
+        let messages = server_messages(output.borrow().clone());
+        assert_eq!(messages.len(), 3);
+        let header: Value = serde_json::from_str(messages[0].to_text().unwrap()).unwrap();
+        assert_eq!(
+            header,
+            json!(
+                {
+                    "type": "WavHeader",
+                    "length": 4,
+                    "request": { "koe": "あ" }
+                }
+            )
+        );
+        assert_eq!(messages[1], Message::binary(b"RIFF".to_vec()));
+        let response: Value = serde_json::from_str(messages[2].to_text().unwrap()).unwrap();
+        assert_eq!(
+            response,
+            json!(
+                {
+                    "isSuccess": true,
+                    "response": { "type": "Wav", "wav": "UklGRg==" },
+                    "request": { "koe": "あ" }
+                }
+            )
+        );
+    }
+}
@@ -0,0 +1,594 @@
+// AquesTalk-proxy - Copyright (C) 2021-2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Home Assistant などが使用する Wyoming プロトコルの TTS サーバー
+
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use aquestalk_proxy::aquestalk::{AquesTalk, Voice};
+use aquestalk_proxy::messages::ResponsePayload;
+use aquestalk_proxyd::aquestalk::{AquesTalkDll, SpeedPolicy};
+use getopts::Options;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use threadpool::ThreadPool;
+
+use crate::GeneralOptions;
+
+const WYOMING_VERSION: &str = "1.5.4";
+const MAX_HEADER_SIZE: u64 = 64 * 1024;
+const SAMPLES_PER_CHUNK: usize = 1024;
+
+struct WyomingProxyOptions {
+    lib_path: PathBuf,
+    speed_policy: SpeedPolicy,
+    addrs: Vec<String>,
+    num_threads: usize,
+    timeout: Option<Duration>,
+    limit: Option<u64>,
+    synthesis: SynthesisOptions,
+}
+
+#[derive(Clone)]
+struct SynthesisOptions {
+    voice_type: String,
+    speed: i32,
+}
+
+fn format_usage(program: &str, opts: Options) -> String {
+    format!(
+        "\
+AquesTalk-proxy Wyoming Mode
+
+USAGE:
+    {} wyoming [OPTIONS]
+
+OPTIONS:
+{}
+",
+        program,
+        opts.usage_with_format(|opts| { opts.collect::<Vec<String>>().join("\n") })
+    )
+}
+
+fn parse_options(
+    GeneralOptions {
+        program,
+        args,
+        lib_path,
+        speed_policy,
+    }: GeneralOptions,
+) -> Result<WyomingProxyOptions, i32> {
+    let mut opts = Options::new();
+    opts.optmulti(
+        "l",
+        "listen",
+        "Address and port to listen on (multiple allowed)",
+        "ADDR",
+    );
+    opts.optopt(
+        "n",
+        "threads",
+        "Number of threads for handling requests",
+        "NUM",
+    );
+    opts.optopt(
+        "",
+        "timeout",
+        "Connection timeout in milliseconds",
+        "MILLIS",
+    );
+    opts.optopt("", "limit", "Max event data and payload size", "BYTES");
+    opts.optopt(
+        "",
+        "voice",
+        "Voice type used when none is requested",
+        "TYPE",
+    );
+    opts.optopt("", "speed", "Speed of synthesized speech", "SPEED");
+    opts.optflag("h", "help", "Print help");
+
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("{}\nERROR: {}", format_usage(&program, opts), f);
+            return Err(1);
+        }
+    };
+
+    if matches.opt_present("h") {
+        println!("{}", format_usage(&program, opts));
+        return Err(0);
+    }
+
+    let addrs = matches.opt_strs("l");
+    let addrs = if !addrs.is_empty() {
+        addrs
+    } else {
+        vec!["127.0.0.1:10200".into(), "[::1]:10200".into()]
+    };
+    let num_threads = matches.opt_get_default("n", 1).unwrap();
+    let timeout = matches
+        .opt_get("timeout")
+        .unwrap()
+        .map(Duration::from_millis);
+    let limit = matches.opt_get("limit").unwrap();
+    let speed = match matches.opt_get_default("speed", 100) {
+        Ok(speed) => speed,
+        Err(err) => {
+            eprintln!(
+                "{}\nERROR: Invalid speed: {}",
+                format_usage(&program, opts),
+                err
+            );
+            return Err(1);
+        }
+    };
+    let synthesis = SynthesisOptions {
+        voice_type: matches.opt_str("voice").unwrap_or_else(|| "f1".to_string()),
+        speed,
+    };
+
+    Ok(WyomingProxyOptions {
+        lib_path,
+        speed_policy,
+        addrs,
+        num_threads,
+        timeout,
+        limit,
+        synthesis,
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+struct EventHeader {
+    #[serde(rename = "type")]
+    event_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<Map<String, Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    payload_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Event {
+    event_type: String,
+    data: Map<String, Value>,
+    payload: Vec<u8>,
+}
+
+fn read_exact_vec<R: Read>(reader: &mut R, length: u64) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(length).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < length {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+fn read_event<R: BufRead>(reader: &mut R, limit: Option<u64>) -> io::Result<Option<Event>> {
+    let mut line = Vec::new();
+    reader
+        .by_ref()
+        .take(MAX_HEADER_SIZE)
+        .read_until(b'\n', &mut line)?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.last() != Some(&b'\n') {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Event header is too long",
+        ));
+    }
+
+    let header: EventHeader = serde_json::from_slice(&line)?;
+    let data_length = header.data_length.unwrap_or(0);
+    let payload_length = header.payload_length.unwrap_or(0);
+    if limit.is_some_and(|limit| data_length + payload_length > limit) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Request is too long",
+        ));
+    }
+
+    // data_length が指定されている場合、データはヘッダーの後に続く
+    let mut data = header.data.unwrap_or_default();
+    if data_length > 0 {
+        let extra: Map<String, Value> =
+            serde_json::from_slice(&read_exact_vec(reader, data_length)?)?;
+        data.extend(extra);
+    }
+    let payload = read_exact_vec(reader, payload_length)?;
+
+    Ok(Some(Event {
+        event_type: header.event_type,
+        data,
+        payload,
+    }))
+}
+
+fn write_event<W: Write>(
+    writer: &mut W,
+    event_type: &str,
+    data: Value,
+    payload: &[u8],
+) -> io::Result<()> {
+    let header = EventHeader {
+        event_type: event_type.to_string(),
+        data: match data {
+            Value::Object(data) if !data.is_empty() => Some(data),
+            _ => None,
+        },
+        data_length: None,
+        payload_length: (!payload.is_empty()).then_some(payload.len() as u64),
+        version: Some(WYOMING_VERSION.to_string()),
+    };
+    serde_json::to_writer(&mut *writer, &header)?;
+    writer.write_all(b"\n")?;
+    writer.write_all(payload)?;
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct AudioFormat {
+    rate: u32,
+    width: u16,
+    channels: u16,
+}
+
+/// WAV データからフォーマットと PCM データを取り出す
+fn parse_wav(wav: &[u8]) -> Option<(AudioFormat, &[u8])> {
+    if wav.len() < 12 || &wav[..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let mut format = None;
+    let mut rest = &wav[12..];
+    while rest.len() >= 8 {
+        let id = &rest[..4];
+        let size = u32::from_le_bytes(rest[4..8].try_into().unwrap()) as usize;
+        let end = size.checked_add(8)?;
+        let body = rest.get(8..end)?;
+        match id {
+            b"fmt " if size >= 16 => {
+                format = Some(AudioFormat {
+                    channels: u16::from_le_bytes(body[2..4].try_into().unwrap()),
+                    rate: u32::from_le_bytes(body[4..8].try_into().unwrap()),
+                    width: u16::from_le_bytes(body[14..16].try_into().unwrap()) / 8,
+                });
+            }
+            b"data" => return format.map(|format| (format, body)),
+            _ => (),
+        }
+        // チャンクは 2 バイト境界に揃えられる
+        rest = rest.get(end + size % 2..).unwrap_or_default();
+    }
+    None
+}
+
+#[derive(Deserialize)]
+struct Synthesize {
+    text: String,
+    #[serde(default)]
+    voice: Option<SynthesizeVoice>,
+}
+
+#[derive(Deserialize)]
+struct SynthesizeVoice {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+fn info(voices: &[Voice]) -> Value {
+    let attribution = json!({ "name": "AQUEST", "url": "https://www.a-quest.com/" });
+    let voices: Vec<_> = voices
+        .iter()
+        .map(|voice| {
+            json!({
+                "name": voice.as_str(),
+                "description": voice.description().unwrap_or(voice.display_name()),
+                "attribution": attribution,
+                "installed": true,
+                "version": null,
+                "languages": ["ja"],
+            })
+        })
+        .collect();
+
+    json!({
+        "tts": [{
+            "name": "aquestalk",
+            "description": "AquesTalk",
+            "attribution": attribution,
+            "installed": true,
+            "version": env!("CARGO_PKG_VERSION"),
+            "voices": voices,
+        }],
+        "asr": [],
+        "handle": [],
+        "intent": [],
+        "wake": [],
+    })
+}
+
+fn error_event(payload: ResponsePayload) -> Value {
+    let (code, message) = match payload {
+        ResponsePayload::Wav { .. } => unreachable!(),
+        ResponsePayload::AquestalkError { message, .. } => ("AquestalkError", message),
+        ResponsePayload::JsonError { message } => ("JsonError", message),
+        ResponsePayload::IoError { message } => ("IoError", message),
+    };
+    json!({ "text": message, "code": code })
+}
+
+fn synthesize<A, W>(
+    writer: &mut W,
+    aqtk: &A,
+    data: Map<String, Value>,
+    options: &SynthesisOptions,
+) -> io::Result<()>
+where
+    A: AquesTalk,
+    W: Write,
+{
+    let result = serde_json::from_value::<Synthesize>(Value::Object(data))
+        .map_err(ResponsePayload::from)
+        .and_then(|request| {
+            let voice_type = request
+                .voice
+                .and_then(|voice| voice.name)
+                .unwrap_or_else(|| options.voice_type.clone());
+            aqtk.synthe(&voice_type, &request.text, options.speed)
+                .map_err(ResponsePayload::from)
+        });
+    let wav = match result {
+        Ok(wav) => wav,
+        Err(err) => return write_event(writer, "error", error_event(err), &[]),
+    };
+
+    let (format, pcm) = match parse_wav(wav.as_ref()) {
+        Some(wav) => wav,
+        None => {
+            let err = ResponsePayload::from_io_error("Invalid WAV data");
+            return write_event(writer, "error", error_event(err), &[]);
+        }
+    };
+    let format_data = json!({
+        "rate": format.rate,
+        "width": format.width,
+        "channels": format.channels,
+    });
+
+    write_event(writer, "audio-start", format_data.clone(), &[])?;
+    let chunk_size = SAMPLES_PER_CHUNK * (format.width * format.channels) as usize;
+    for chunk in pcm.chunks(chunk_size.max(1)) {
+        write_event(writer, "audio-chunk", format_data.clone(), chunk)?;
+    }
+    write_event(writer, "audio-stop", json!({}), &[])
+}
+
+fn handle_event<A, W>(
+    event: Event,
+    writer: &mut W,
+    aqtk: &A,
+    voices: &[Voice],
+    options: &SynthesisOptions,
+) -> io::Result<()>
+where
+    A: AquesTalk,
+    W: Write,
+{
+    match event.event_type.as_str() {
+        "describe" => write_event(writer, "info", info(voices), &[])?,
+        "synthesize" => synthesize(writer, aqtk, event.data, options)?,
+        "ping" => write_event(writer, "pong", Value::Object(event.data), &[])?,
+        // 未対応のイベントは無視する
+        _ => return Ok(()),
+    }
+    writer.flush()
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    aqtk: AquesTalkDll,
+    timeout: Option<Duration>,
+    limit: Option<u64>,
+    options: SynthesisOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    stream.set_read_timeout(timeout)?;
+    let mut reader = BufReader::new(&stream);
+    let mut writer = BufWriter::new(&stream);
+    let voices = aqtk.voices();
+
+    while let Some(event) = read_event(&mut reader, limit)? {
+        handle_event(event, &mut writer, &aqtk, &voices, &options)?;
+    }
+
+    stream.shutdown(Shutdown::Write)?;
+    Ok(())
+}
+
+pub fn run_wyoming_proxy(options: GeneralOptions) -> i32 {
+    let options = match parse_options(options) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+
+    let aqtk = AquesTalkDll::new(&options.lib_path)
+        .unwrap()
+        .with_speed_policy(options.speed_policy);
+    let pool = Arc::new(Mutex::new(ThreadPool::new(options.num_threads)));
+
+    (options.addrs)
+        .iter()
+        .map(|addr| {
+            let listener = TcpListener::bind(addr).unwrap();
+            let aqtk = aqtk.clone();
+            let timeout = options.timeout;
+            let limit = options.limit;
+            let synthesis = options.synthesis.clone();
+            let pool = Arc::clone(&pool);
+
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = stream.unwrap();
+                    let aqtk = aqtk.clone();
+                    let synthesis = synthesis.clone();
+
+                    pool.lock().unwrap().execute(move || {
+                        handle_connection(stream, aqtk, timeout, limit, synthesis)
+                            .unwrap_or_else(|err| eprintln!("{}", err));
+                    });
+                }
+            })
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .for_each(|t| t.join().unwrap());
+
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use aquestalk_proxy::aquestalk::{AquesTalk, SynthesisRequest, Voice};
+    use aquestalk_proxy::ClientError;
+    use serde_json::{json, Value};
+
+    use super::{handle_event, parse_wav, read_event, AudioFormat, Event, SynthesisOptions};
+
+    /// 8kHz 16bit モノラルの無音を返す
+    struct Silence;
+
+    impl AquesTalk for Silence {
+        type Wav = Vec<u8>;
+
+        fn synthe_request(&self, request: &SynthesisRequest) -> Result<Vec<u8>, ClientError> {
+            if request.voice_type != "f1" {
+                return Err(ClientError::UnknownVoice {
+                    voice_type: request.voice_type.clone(),
+                });
+            }
+
+            let samples = 1500;
+            let mut wav = Vec::new();
+            wav.extend_from_slice(b"RIFF");
+            wav.extend_from_slice(&(36 + samples * 2u32).to_le_bytes());
+            wav.extend_from_slice(b"WAVEfmt ");
+            wav.extend_from_slice(&16u32.to_le_bytes());
+            wav.extend_from_slice(&[1, 0, 1, 0]);
+            wav.extend_from_slice(&8000u32.to_le_bytes());
+            wav.extend_from_slice(&16000u32.to_le_bytes());
+            wav.extend_from_slice(&[2, 0, 16, 0]);
+            wav.extend_from_slice(b"data");
+            wav.extend_from_slice(&(samples * 2).to_le_bytes());
+            wav.resize(wav.len() + samples as usize * 2, 0);
+            Ok(wav)
+        }
+    }
+
+    fn read_events(output: Vec<u8>) -> Vec<Event> {
+        let mut reader = Cursor::new(output);
+        let mut events = Vec::new();
+        while let Some(event) = read_event(&mut reader, None).unwrap() {
+            events.push(event);
+        }
+        events
+    }
+
+    fn request(input: &str) -> Event {
+        read_event(&mut Cursor::new(input.as_bytes()), None)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_read_event() {
+        let event = request(
+            "{\"type\":\"synthesize\",\"data\":{\"text\":\"あ\"},\"data_length\":20,\"payload_length\":2}\n\
+             {\"voice\":{\"name\":1}}\x01\x02",
+        );
+        assert_eq!(event.event_type, "synthesize");
+        assert_eq!(
+            Value::Object(event.data),
+            json!({ "text": "あ", "voice": { "name": 1 } })
+        );
+        assert_eq!(event.payload, [1, 2]);
+    }
+
+    #[test]
+    fn test_parse_wav() {
+        let wav = Silence.synthe("f1", "", 100).unwrap();
+        let (format, pcm) = parse_wav(&wav).unwrap();
+        assert_eq!(
+            format,
+            AudioFormat {
+                rate: 8000,
+                width: 2,
+                channels: 1
+            }
+        );
+        assert_eq!(pcm.len(), 3000);
+        assert!(parse_wav(b"RIFF").is_none());
+        assert!(parse_wav(b"RIFF\0\0\0\0WAVEdata\xff\xff\xff\xff").is_none());
+    }
+
+    #[test]
+    fn test_synthesize() {
+        let options = SynthesisOptions {
+            voice_type: "f1".to_string(),
+            speed: 100,
+        };
+
+        let mut output = Vec::new();
+        let event = request("{\"type\":\"synthesize\",\"data\":{\"text\":\"あ\"}}\n");
+        handle_event(event, &mut output, &Silence, &[Voice::F1], &options).unwrap();
+        let events = read_events(output);
+        let types: Vec<_> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(
+            types,
+            ["audio-start", "audio-chunk", "audio-chunk", "audio-stop"]
+        );
+        assert_eq!(
+            Value::Object(events[1].data.clone()),
+            json!({ "rate": 8000, "width": 2, "channels": 1 })
+        );
+        assert_eq!(events[1].payload.len(), 2048);
+        assert_eq!(events[2].payload.len(), 952);
+
+        let mut output = Vec::new();
+        let event = request(
+            "{\"type\":\"synthesize\",\"data\":{\"text\":\"あ\",\"voice\":{\"name\":\"m1\"}}}\n",
+        );
+        handle_event(event, &mut output, &Silence, &[Voice::F1], &options).unwrap();
+        let events = read_events(output);
+        assert_eq!(events[0].event_type, "error");
+        assert_eq!(events[0].data["code"], "AquestalkError");
+    }
+}
@@ -1,4 +1,4 @@
-// Copyright (c) 2021-2022 Na-x4
+// Copyright (c) 2021-2026 Na-x4
 //
 // Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
 // https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
@@ -7,16 +7,106 @@
 // except according to those terms.
 
 use std::fmt;
+#[cfg(feature = "tokio")]
+use std::future::Future;
+use std::sync::Arc;
+
+mod cached;
+pub use cached::{fnv1a, CacheStats, Cached};
+
+mod cassette;
+pub use cassette::{Matching, Recorder, Replayer};
 
 mod koe;
 pub use koe::Koe;
 
-use crate::messages::ResponsePayload;
+mod request;
+pub use request::SynthesisRequest;
+
+mod routing;
+pub use routing::{Fallback, LeastBusy, RoundRobin, VoiceRouter};
+
+mod voice;
+pub use voice::{Gender, Voice};
 
+use crate::ClientError;
+
+/// 音声記号列を WAV データに変換する
+///
+/// 実装するのは `synthe_request` だけで、`synthe` はそれを呼ぶ。
+///
+/// ```
+/// use aquestalk_proxy::aquestalk::{AquesTalk, SynthesisRequest};
+/// use aquestalk_proxy::ClientError;
+///
+/// struct Echo;
+///
+/// impl AquesTalk for Echo {
+///     type Wav = Vec<u8>;
+///     fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+///         Ok(request.koe.as_bytes().to_vec())
+///     }
+/// }
+///
+/// let wav = Echo.synthe("f1", "あ", 100).unwrap();
+/// assert_eq!(wav, "あ".as_bytes());
+/// ```
 pub trait AquesTalk {
     type Wav: AsRef<[u8]>;
-    fn synthe(&self, voice_type: &str, koe: &str, speed: i32)
-        -> Result<Self::Wav, ResponsePayload>;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError>;
+
+    fn synthe(&self, voice_type: &str, koe: &str, speed: i32) -> Result<Self::Wav, ClientError> {
+        self.synthe_request(
+            &SynthesisRequest::new(koe)
+                .with_voice_type(voice_type)
+                .with_speed(speed),
+        )
+    }
+}
+
+/// 異なる実装を `Box<dyn AquesTalk<Wav = Vec<u8>>>` などにまとめて使えるようにする
+macro_rules! impl_aquestalk_for_pointer {
+    ($($pointer:ty),*) => {$(
+        impl<A> AquesTalk for $pointer
+        where
+            A: AquesTalk + ?Sized,
+        {
+            type Wav = A::Wav;
+            fn synthe_request(
+                &self,
+                request: &SynthesisRequest,
+            ) -> Result<Self::Wav, ClientError> {
+                (**self).synthe_request(request)
+            }
+        }
+    )*};
+}
+
+impl_aquestalk_for_pointer!(&A, Box<A>, Arc<A>);
+
+/// [`AquesTalk`] の非同期版
+#[cfg(feature = "tokio")]
+pub trait AsyncAquesTalk {
+    type Wav: AsRef<[u8]>;
+    fn synthe_request(
+        &self,
+        request: &SynthesisRequest,
+    ) -> impl Future<Output = Result<Self::Wav, ClientError>> + Send;
+
+    fn synthe(
+        &self,
+        voice_type: &str,
+        koe: &str,
+        speed: i32,
+    ) -> impl Future<Output = Result<Self::Wav, ClientError>> + Send
+    where
+        Self: Sync,
+    {
+        let request = SynthesisRequest::new(koe)
+            .with_voice_type(voice_type)
+            .with_speed(speed);
+        async move { self.synthe_request(&request).await }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
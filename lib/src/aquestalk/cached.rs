@@ -0,0 +1,379 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{AquesTalk, SynthesisRequest};
+use crate::messages::Request;
+use crate::ClientError;
+
+type Key = SynthesisRequest;
+
+/// [`Cached`] の統計情報
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// メモリ上のキャッシュから返した回数
+    pub hits: u64,
+    /// ディスク上のキャッシュから返した回数
+    pub disk_hits: u64,
+    /// 内側の [`AquesTalk`] で合成した回数 (失敗した場合も含む)
+    pub misses: u64,
+    /// メモリ上に保持している項目数
+    pub entries: usize,
+    /// メモリ上に保持している WAV データの合計バイト数
+    pub bytes: usize,
+}
+
+struct Entry {
+    wav: Arc<[u8]>,
+    created: Instant,
+    used: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<Key, Entry>,
+    /// 最後に使われた順の索引
+    order: BTreeMap<u64, Key>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl Lru {
+    fn get(&mut self, key: &Key, ttl: Option<Duration>) -> Option<Arc<[u8]>> {
+        let created = self.entries.get(key)?.created;
+        if ttl.is_some_and(|ttl| created.elapsed() > ttl) {
+            self.remove(key);
+            return None;
+        }
+
+        self.tick += 1;
+        let entry = self.entries.get_mut(key).unwrap();
+        self.order.remove(&entry.used);
+        entry.used = self.tick;
+        self.order.insert(self.tick, key.clone());
+        Some(Arc::clone(&entry.wav))
+    }
+
+    fn insert(&mut self, key: Key, wav: Arc<[u8]>, created: Instant, capacity: usize) {
+        self.remove(&key);
+        if wav.len() > capacity {
+            return;
+        }
+
+        self.tick += 1;
+        self.stats.bytes += wav.len();
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                wav,
+                created,
+                used: self.tick,
+            },
+        );
+
+        while self.stats.bytes > capacity {
+            let (_, key) = self.order.pop_first().unwrap();
+            let entry = self.entries.remove(&key).unwrap();
+            self.stats.bytes -= entry.wav.len();
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.used);
+            self.stats.bytes -= entry.wav.len();
+        }
+    }
+}
+
+/// 合成結果をキャッシュする [`AquesTalk`]
+///
+/// 声種、音声記号列、発話速度が同じ要求には保持している WAV データを返す。
+/// メモリ上では合計 `capacity` バイトを超えないよう最も長く使われていないものから破棄する。
+/// [`Cached::with_dir`] を指定した場合はディレクトリにも保存し、プロセスをまたいで再利用する。
+/// 合成に失敗した結果はキャッシュしない。
+pub struct Cached<A> {
+    inner: A,
+    capacity: usize,
+    ttl: Option<Duration>,
+    dir: Option<PathBuf>,
+    lru: Mutex<Lru>,
+    writes: AtomicU64,
+}
+
+impl<A> Cached<A>
+where
+    A: AquesTalk,
+{
+    pub fn new(inner: A, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            ttl: None,
+            dir: None,
+            lru: Mutex::new(Lru::default()),
+            writes: AtomicU64::new(0),
+        }
+    }
+
+    /// キャッシュの有効期間 (ディスク上のファイルは更新日時から数える)
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// WAV データを保存するディレクトリ
+    ///
+    /// WAV データの内容ではなく要求のハッシュをファイル名 (`<ハッシュ>.wav`) にし、
+    /// 照合に使う要求を隣 (`<ハッシュ>.json`) に保存する。
+    pub fn with_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let lru = self.lru.lock().unwrap();
+        CacheStats {
+            entries: lru.entries.len(),
+            ..lru.stats
+        }
+    }
+
+    /// メモリ上のキャッシュを破棄する
+    pub fn clear(&self) {
+        let mut lru = self.lru.lock().unwrap();
+        lru.entries.clear();
+        lru.order.clear();
+        lru.stats.bytes = 0;
+    }
+
+    fn path(&self, key: &Key) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        Some(dir.join(format!("{:032x}.wav", digest(key))))
+    }
+
+    /// ディスク上のキャッシュを読み、WAV データと保存された時刻を返す
+    ///
+    /// ハッシュが衝突した別の要求の WAV データを返さないよう、隣に保存した要求と照合する。
+    fn load(&self, key: &Key) -> Option<(Vec<u8>, Instant)> {
+        let path = self.path(key)?;
+        if fs::read(path.with_extension("json")).ok()? != encode(key) {
+            return None;
+        }
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()?
+            .elapsed()
+            .unwrap_or_default();
+        if self.ttl.is_some_and(|ttl| age > ttl) {
+            let _ = fs::remove_file(&path);
+            let _ = fs::remove_file(path.with_extension("json"));
+            return None;
+        }
+
+        let wav = fs::read(&path).ok()?;
+        Some((
+            wav,
+            Instant::now().checked_sub(age).unwrap_or_else(Instant::now),
+        ))
+    }
+
+    /// WAV データと、照合に使う要求を保存する
+    fn store(&self, key: &Key, wav: &[u8]) -> io::Result<()> {
+        let Some(path) = self.path(key) else {
+            return Ok(());
+        };
+        fs::create_dir_all(path.parent().unwrap())?;
+        self.write(&path, wav)?;
+        self.write(&path.with_extension("json"), &encode(key))
+    }
+
+    /// 書きかけのファイルを読まないよう一時ファイルに書いてから置き換える
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let temp = path.with_extension(format!(
+            "{}-{}.tmp",
+            process::id(),
+            self.writes.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temp, contents)?;
+        fs::rename(&temp, path).inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })
+    }
+}
+
+impl<A> AquesTalk for Cached<A>
+where
+    A: AquesTalk,
+{
+    type Wav = Arc<[u8]>;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        {
+            let mut lru = self.lru.lock().unwrap();
+            if let Some(wav) = lru.get(request, self.ttl) {
+                lru.stats.hits += 1;
+                return Ok(wav);
+            }
+        }
+
+        let (wav, created) = match self.load(request) {
+            Some((wav, created)) => {
+                self.lru.lock().unwrap().stats.disk_hits += 1;
+                (Arc::from(wav), created)
+            }
+            None => {
+                self.lru.lock().unwrap().stats.misses += 1;
+                let wav = self.inner.synthe_request(request)?;
+                let wav: Arc<[u8]> = Arc::from(wav.as_ref());
+                // ディスクへの保存に失敗しても合成結果はそのまま返す
+                let _ = self.store(request, &wav);
+                (wav, Instant::now())
+            }
+        };
+
+        let mut lru = self.lru.lock().unwrap();
+        lru.insert(request.clone(), Arc::clone(&wav), created, self.capacity);
+        Ok(wav)
+    }
+}
+
+/// 要求を送信するときの JSON
+fn encode(key: &Key) -> Vec<u8> {
+    serde_json::to_vec(&Request::from(key)).unwrap()
+}
+
+/// 要求をキーとするファイル名に使うハッシュ
+///
+/// 項目を漏らさないよう、要求を送信するときの JSON から求める。
+fn digest(key: &Key) -> u128 {
+    fnv1a(&encode(key))
+}
+
+/// 128 ビットの FNV-1a ハッシュ
+///
+/// ファイル名などに使うため、Rust のバージョンによらず同じ値になるものを使う。
+pub fn fnv1a(bytes: &[u8]) -> u128 {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u128).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::aquestalk::{AquesTalk, CacheStats, Cached, SynthesisRequest};
+    use crate::ClientError;
+
+    /// 音声記号列をそのまま返し、呼び出し回数を数える
+    #[derive(Default)]
+    struct Echo {
+        calls: AtomicUsize,
+    }
+
+    impl AquesTalk for Echo {
+        type Wav = Vec<u8>;
+        fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if request.koe == "ERR" {
+                return Err(ClientError::AquesTalk {
+                    code: Some(105),
+                    message: String::new(),
+                });
+            }
+            Ok(request.koe.as_bytes().to_vec())
+        }
+    }
+
+    #[test]
+    fn lru() {
+        let aqtk = Cached::new(Echo::default(), 8);
+        assert_eq!(aqtk.synthe("f1", "aaaa", 100).unwrap().as_ref(), b"aaaa");
+        assert_eq!(aqtk.synthe("f1", "bbbb", 100).unwrap().as_ref(), b"bbbb");
+        aqtk.synthe("f1", "aaaa", 100).unwrap();
+        // "bbbb" が破棄される
+        aqtk.synthe("f1", "cccc", 100).unwrap();
+        aqtk.synthe("f1", "aaaa", 100).unwrap();
+        aqtk.synthe("f2", "aaaa", 100).unwrap();
+        aqtk.synthe("f1", "bbbb", 100).unwrap();
+        // 容量を超えるものは保持しない
+        aqtk.synthe("f1", "too long!", 200).unwrap();
+        assert!(aqtk.synthe("f1", "ERR", 100).is_err());
+        assert!(aqtk.synthe("f1", "ERR", 100).is_err());
+
+        assert_eq!(
+            aqtk.stats(),
+            CacheStats {
+                hits: 2,
+                disk_hits: 0,
+                misses: 8,
+                entries: 2,
+                bytes: 8,
+            }
+        );
+        assert_eq!(aqtk.inner.calls.load(Ordering::SeqCst), 8);
+    }
+
+    #[test]
+    fn ttl() {
+        let aqtk = Cached::new(Echo::default(), 1024).with_ttl(Duration::from_millis(50));
+        aqtk.synthe("f1", "a", 100).unwrap();
+        aqtk.synthe("f1", "a", 100).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        aqtk.synthe("f1", "a", 100).unwrap();
+        assert_eq!(aqtk.stats().hits, 1);
+        assert_eq!(aqtk.stats().misses, 2);
+    }
+
+    #[test]
+    fn dir() {
+        let dir = env::temp_dir().join(format!("aquestalk-proxy-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let aqtk = Cached::new(Echo::default(), 1024).with_dir(&dir);
+        aqtk.synthe("f1", "a", 100).unwrap();
+
+        let aqtk = Cached::new(Echo::default(), 1024).with_dir(&dir);
+        assert_eq!(aqtk.synthe("f1", "a", 100).unwrap().as_ref(), b"a");
+        assert_eq!(aqtk.synthe("f1", "a", 100).unwrap().as_ref(), b"a");
+        assert_eq!(aqtk.synthe("f1", "a", 50).unwrap().as_ref(), b"a");
+        assert_eq!((aqtk.stats().hits, aqtk.stats().disk_hits), (1, 1));
+        assert_eq!(aqtk.inner.calls.load(Ordering::SeqCst), 1);
+        // WAV データと要求を 1 つずつ保存する
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+
+        // 保存した要求と一致しない WAV データは使わない
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                fs::write(path, "{}").unwrap();
+            }
+        }
+        let aqtk = Cached::new(Echo::default(), 1024).with_dir(&dir);
+        assert_eq!(aqtk.synthe("f1", "a", 100).unwrap().as_ref(), b"a");
+        assert_eq!(aqtk.stats().disk_hits, 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
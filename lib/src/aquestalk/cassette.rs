@@ -0,0 +1,433 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! 合成結果を記録して再生する
+//!
+//! カセットはディレクトリで、要求と結果を 1 行ずつ記録した `cassette.jsonl` と
+//! WAV データのファイルからなる。WAV データのファイル名は内容のハッシュなので、
+//! 同じ音声は 1 つのファイルにまとめられる。ハッシュが衝突した場合は `-1` などの連番を付ける。
+//!
+//! ```text
+//! {"request":{"type":"f1","speed":100,"koe":"こんにちわ"},"wav":"0123...cdef.wav"}
+//! {"request":{"type":"f1","speed":100,"koe":"?"},"error":{"type":"AquestalkError","code":105,"message":"..."},"kind":"AquesTalk"}
+//! ```
+//!
+//! `error` は応答と同じ形式で、`kind` には再生時に同じ [`ClientError`] に戻せるようその種類を記録する。
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use super::cached::fnv1a;
+use super::{AquesTalk, SynthesisRequest};
+use crate::messages::{Request, ResponsePayload};
+use crate::ClientError;
+
+const CASSETTE: &str = "cassette.jsonl";
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    request: Request,
+    /// WAV データのファイル名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wav: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<ResponsePayload>,
+    /// `error` の [`ClientError`] の種類 (無い場合は応答と同様に `error` と要求から求める)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<Kind>,
+}
+
+/// [`ClientError`] の種類
+///
+/// `Timeout` などは `error` では `IoError` になるため、区別できるよう別に記録する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Kind {
+    Transport,
+    Server,
+    Protocol,
+    AquesTalk,
+    UnknownVoice,
+    InvalidSpeed,
+    Closed,
+    Timeout,
+    NotRecorded,
+}
+
+impl From<&ClientError> for Kind {
+    fn from(err: &ClientError) -> Self {
+        match err {
+            ClientError::Transport { .. } => Self::Transport,
+            ClientError::Server { .. } => Self::Server,
+            ClientError::Protocol { .. } => Self::Protocol,
+            ClientError::AquesTalk { .. } => Self::AquesTalk,
+            ClientError::UnknownVoice { .. } => Self::UnknownVoice,
+            ClientError::InvalidSpeed { .. } => Self::InvalidSpeed,
+            ClientError::Closed => Self::Closed,
+            ClientError::Timeout { .. } => Self::Timeout,
+            ClientError::NotRecorded { .. } => Self::NotRecorded,
+        }
+    }
+}
+
+/// 記録したエラーを [`ClientError`] に戻す
+fn restore(
+    payload: ResponsePayload,
+    kind: Option<Kind>,
+    request: &SynthesisRequest,
+) -> ClientError {
+    let message = match &payload {
+        ResponsePayload::AquestalkError { message, .. }
+        | ResponsePayload::JsonError { message }
+        | ResponsePayload::IoError { message } => message.clone(),
+        ResponsePayload::Wav { .. } => String::new(),
+    };
+    match kind {
+        Some(Kind::Transport) => ClientError::Transport { message },
+        Some(Kind::Server) => ClientError::Server { message },
+        Some(Kind::UnknownVoice) => ClientError::UnknownVoice {
+            voice_type: request.voice_type.clone(),
+        },
+        Some(Kind::InvalidSpeed) => ClientError::InvalidSpeed {
+            speed: request.speed,
+        },
+        Some(Kind::Closed) => ClientError::Closed,
+        Some(Kind::Timeout) => ClientError::Timeout { message },
+        Some(Kind::NotRecorded) => ClientError::NotRecorded { message },
+        Some(Kind::Protocol | Kind::AquesTalk) => payload.into(),
+        None => ClientError::from_payload(payload, request),
+    }
+}
+
+/// 内側の [`AquesTalk`] への要求と結果をカセットに記録する [`AquesTalk`]
+pub struct Recorder<A> {
+    inner: A,
+    dir: PathBuf,
+    cassette: Mutex<File>,
+}
+
+impl<A> Recorder<A>
+where
+    A: AquesTalk,
+{
+    /// `dir` にカセットを作る (既に記録がある場合は消去する)
+    pub fn create<P: Into<PathBuf>>(inner: A, dir: P) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let cassette = File::create(dir.join(CASSETTE))?;
+        Ok(Self {
+            inner,
+            dir,
+            cassette: Mutex::new(cassette),
+        })
+    }
+
+    /// WAV データを保存してファイル名を返す
+    ///
+    /// 同じ名前のファイルは内容が一致する場合だけ使い、一致しない場合は連番を付けた名前にする。
+    fn store(&self, wav: &[u8]) -> io::Result<String> {
+        let hash = fnv1a(wav);
+        let mut seq = 0;
+        loop {
+            let name = match seq {
+                0 => format!("{:032x}.wav", hash),
+                seq => format!("{:032x}-{}.wav", hash, seq),
+            };
+            match fs::read(self.dir.join(&name)) {
+                Ok(stored) if stored == wav => return Ok(name),
+                Ok(_) => seq += 1,
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    fs::write(self.dir.join(&name), wav)?;
+                    return Ok(name);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn record(&self, request: Request, result: Result<&[u8], &ClientError>) -> io::Result<()> {
+        // 同じ WAV データを同時に保存しないよう、保存する間もロックしておく
+        let mut cassette = self.cassette.lock().unwrap();
+        let entry = match result {
+            Ok(wav) => Entry {
+                request,
+                wav: Some(self.store(wav)?),
+                error: None,
+                kind: None,
+            },
+            Err(err) => Entry {
+                request,
+                wav: None,
+                error: Some(err.clone().into()),
+                kind: Some(err.into()),
+            },
+        };
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        cassette.write_all(&line)?;
+        cassette.flush()
+    }
+}
+
+impl<A> AquesTalk for Recorder<A>
+where
+    A: AquesTalk,
+{
+    type Wav = A::Wav;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        let result = self.inner.synthe_request(request);
+        self.record(request.into(), result.as_ref().map(|wav| wav.as_ref()))
+            .map_err(ClientError::from)?;
+        result
+    }
+}
+
+/// [`Replayer`] が要求と記録を照合する方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Matching {
+    /// 記録した順に、声種、音声記号列、発話速度が全て一致する要求だけを受け付ける
+    Strict,
+    /// 順序を問わず、全て一致する記録が無ければ音声記号列だけが一致する記録を使う
+    Lenient,
+}
+
+struct Recorded {
+    request: SynthesisRequest,
+    result: Result<Vec<u8>, ClientError>,
+}
+
+/// [`Recorder`] で記録したカセットから結果を返す [`AquesTalk`]
+///
+/// 記録に無い要求には [`ClientError::NotRecorded`] を返す。
+pub struct Replayer {
+    recorded: Vec<Recorded>,
+    matching: Matching,
+    /// [`Matching::Strict`] で次に照合する記録
+    position: Mutex<usize>,
+}
+
+impl Replayer {
+    pub fn open<P: AsRef<Path>>(dir: P, matching: Matching) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        let mut recorded = Vec::new();
+        for line in BufReader::new(File::open(dir.join(CASSETTE))?).lines() {
+            let entry: Entry = serde_json::from_str(&line?)?;
+            let request = SynthesisRequest::from(entry.request);
+            let result = match (entry.wav, entry.error) {
+                (Some(name), None) => Ok(read_wav(dir, &name)?),
+                (None, Some(err)) => Err(restore(err, entry.kind, &request)),
+                _ => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        "Either wav or error must be recorded.",
+                    ))
+                }
+            };
+            recorded.push(Recorded { request, result });
+        }
+
+        Ok(Self {
+            recorded,
+            matching,
+            position: Mutex::new(0),
+        })
+    }
+
+    /// [`Matching::Strict`] でまだ再生していない記録の数
+    pub fn remaining(&self) -> usize {
+        self.recorded.len() - *self.position.lock().unwrap()
+    }
+
+    fn find(&self, request: &SynthesisRequest) -> Option<&Recorded> {
+        match self.matching {
+            Matching::Strict => {
+                let mut position = self.position.lock().unwrap();
+                let recorded = self
+                    .recorded
+                    .get(*position)
+                    .filter(|recorded| recorded.request == *request)?;
+                *position += 1;
+                Some(recorded)
+            }
+            Matching::Lenient => self
+                .recorded
+                .iter()
+                .find(|recorded| recorded.request == *request)
+                .or_else(|| {
+                    self.recorded
+                        .iter()
+                        .find(|recorded| recorded.request.koe == request.koe)
+                }),
+        }
+    }
+}
+
+/// WAV データのファイルを読み、内容がファイル名のハッシュと一致するか確かめる
+fn read_wav(dir: &Path, name: &str) -> io::Result<Vec<u8>> {
+    let wav = fs::read(dir.join(name))?;
+    if !name.starts_with(&format!("{:032x}", fnv1a(&wav))) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{} does not match its content.", name),
+        ));
+    }
+    Ok(wav)
+}
+
+impl AquesTalk for Replayer {
+    type Wav = Vec<u8>;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        match self.find(request) {
+            Some(recorded) => recorded.result.clone(),
+            None => Err(ClientError::NotRecorded {
+                message: format!(
+                    "No recorded response for (type: {}, koe: {}, speed: {}).",
+                    request.voice_type, request.koe, request.speed
+                ),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+
+    use super::fnv1a;
+    use crate::aquestalk::{AquesTalk, Matching, Recorder, Replayer, SynthesisRequest};
+    use crate::ClientError;
+
+    /// 声種と音声記号列を返す
+    struct Echo;
+
+    impl AquesTalk for Echo {
+        type Wav = Vec<u8>;
+        fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+            match request.koe.as_str() {
+                "ERR" => {
+                    return Err(ClientError::AquesTalk {
+                        code: Some(105),
+                        message: "x".to_string(),
+                    })
+                }
+                "TIMEOUT" => {
+                    return Err(ClientError::Timeout {
+                        message: "x".to_string(),
+                    })
+                }
+                _ => (),
+            }
+            if request.voice_type == "none" {
+                return Err(ClientError::UnknownVoice {
+                    voice_type: request.voice_type.clone(),
+                });
+            }
+            Ok(format!("{}:{}", request.voice_type, request.koe).into_bytes())
+        }
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let dir = env::temp_dir().join(format!("aquestalk-proxy-cassette-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let recorder = Recorder::create(Echo, &dir).unwrap();
+        assert_eq!(recorder.synthe("f1", "a", 100).unwrap(), b"f1:a");
+        assert_eq!(recorder.synthe("m1", "b", 100).unwrap(), b"m1:b");
+        assert!(recorder.synthe("f1", "ERR", 100).is_err());
+        assert_eq!(recorder.synthe("f1", "a", 100).unwrap(), b"f1:a");
+        drop(recorder);
+        // 同じ音声は 1 つのファイルにまとめる
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        let replayer = Replayer::open(&dir, Matching::Strict).unwrap();
+        assert_eq!(replayer.synthe("f1", "a", 100).unwrap(), b"f1:a");
+        assert!(matches!(
+            replayer.synthe("f1", "b", 100),
+            Err(ClientError::NotRecorded { .. })
+        ));
+        assert_eq!(replayer.synthe("m1", "b", 100).unwrap(), b"m1:b");
+        assert!(matches!(
+            replayer.synthe("f1", "ERR", 100),
+            Err(ClientError::AquesTalk {
+                code: Some(105),
+                ..
+            })
+        ));
+        assert_eq!(replayer.remaining(), 1);
+        assert_eq!(replayer.synthe("f1", "a", 100).unwrap(), b"f1:a");
+        assert!(replayer.synthe("f1", "a", 100).is_err());
+
+        let replayer = Replayer::open(&dir, Matching::Lenient).unwrap();
+        assert_eq!(replayer.synthe("m1", "b", 100).unwrap(), b"m1:b");
+        assert_eq!(replayer.synthe("m1", "b", 100).unwrap(), b"m1:b");
+        assert_eq!(replayer.synthe("f2", "a", 50).unwrap(), b"f1:a");
+        assert!(replayer.synthe("f1", "c", 100).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn error_kind() {
+        let dir = env::temp_dir().join(format!("aquestalk-proxy-kind-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let recorder = Recorder::create(Echo, &dir).unwrap();
+        assert!(recorder.synthe("f1", "TIMEOUT", 100).is_err());
+        assert!(recorder.synthe("none", "a", 100).is_err());
+        drop(recorder);
+
+        // `IoError` などになる種類も記録した通りに再生する
+        let replayer = Replayer::open(&dir, Matching::Strict).unwrap();
+        assert_eq!(
+            replayer.synthe("f1", "TIMEOUT", 100),
+            Err(ClientError::Timeout {
+                message: "x".to_string()
+            })
+        );
+        assert_eq!(
+            replayer.synthe("none", "a", 100),
+            Err(ClientError::UnknownVoice {
+                voice_type: "none".to_string()
+            })
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn collision() {
+        let dir = env::temp_dir().join(format!("aquestalk-proxy-collision-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // 前回の記録などで同じ名前のファイルが既にあっても上書きや流用をしない
+        let name = format!("{:032x}.wav", fnv1a(b"f1:a"));
+        fs::write(dir.join(&name), b"other").unwrap();
+        let recorder = Recorder::create(Echo, &dir).unwrap();
+        assert_eq!(recorder.synthe("f1", "a", 100).unwrap(), b"f1:a");
+        drop(recorder);
+        assert_eq!(fs::read(dir.join(&name)).unwrap(), b"other");
+
+        let replayer = Replayer::open(&dir, Matching::Strict).unwrap();
+        assert_eq!(replayer.synthe("f1", "a", 100).unwrap(), b"f1:a");
+
+        // 内容がファイル名と一致しない WAV データは読まない
+        let name = format!("{:032x}-1.wav", fnv1a(b"f1:a"));
+        fs::write(dir.join(name), b"other").unwrap();
+        assert!(Replayer::open(&dir, Matching::Strict).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
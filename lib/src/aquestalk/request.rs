@@ -0,0 +1,84 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::messages::{default_speed, default_type, Request, SPEED_RANGE};
+use crate::ClientError;
+
+/// [`AquesTalk::synthe_request`](super::AquesTalk::synthe_request) に渡す合成の要求
+///
+/// 項目が増えても既存の実装や呼び出し側を変更せずに済むよう、`with_*` で組み立てる。
+///
+/// ```
+/// use aquestalk_proxy::aquestalk::SynthesisRequest;
+///
+/// let request = SynthesisRequest::new("こんにちわ").with_voice_type("m1").with_speed(120);
+/// assert_eq!(request.voice_type, "m1");
+/// assert!(request.validate().is_ok());
+/// assert!(request.with_speed(400).validate().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct SynthesisRequest {
+    /// 声種 (デフォルト: `f1`)
+    pub voice_type: String,
+    /// 音声記号列
+    pub koe: String,
+    /// 発話速度 [%] (デフォルト: 100)
+    pub speed: i32,
+}
+
+impl SynthesisRequest {
+    pub fn new<K: Into<String>>(koe: K) -> Self {
+        Self {
+            voice_type: default_type(),
+            koe: koe.into(),
+            speed: default_speed(),
+        }
+    }
+
+    pub fn with_voice_type<V: Into<String>>(mut self, voice_type: V) -> Self {
+        self.voice_type = voice_type.into();
+        self
+    }
+
+    pub fn with_speed(mut self, speed: i32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// 発話速度が [`SPEED_RANGE`] の範囲内か確かめる
+    ///
+    /// 範囲外の発話速度はデーモンが `--speed-policy` に従って拒否するか丸めるため、
+    /// ライブラリのクライアントは送信前にこれを呼ばない。送信せずに拒否する場合は呼び出し側で呼ぶ。
+    pub fn validate(&self) -> Result<(), ClientError> {
+        if !SPEED_RANGE.contains(&self.speed) {
+            return Err(ClientError::InvalidSpeed { speed: self.speed });
+        }
+        Ok(())
+    }
+}
+
+impl From<Request> for SynthesisRequest {
+    fn from(request: Request) -> Self {
+        Self {
+            voice_type: request.voice_type,
+            koe: request.koe,
+            speed: request.speed,
+        }
+    }
+}
+
+impl From<&SynthesisRequest> for Request {
+    fn from(request: &SynthesisRequest) -> Self {
+        Self {
+            voice_type: request.voice_type.clone(),
+            speed: request.speed,
+            koe: request.koe.clone(),
+        }
+    }
+}
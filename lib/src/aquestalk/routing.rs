@@ -0,0 +1,430 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{AquesTalk, SynthesisRequest};
+use crate::ClientError;
+
+/// バックエンド自体の障害とみなすエラーか
+///
+/// `AquestalkError` や `JsonError` は要求の内容によるものなので、バックエンドは正常とみなす。
+fn is_backend_failure(err: &ClientError) -> bool {
+    err.is_transport()
+}
+
+/// 処理中の要求を数え、パニックした場合も含めて戻るときに減らす
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl<'a> InFlight<'a> {
+    fn new(count: &'a AtomicUsize) -> Self {
+        count.fetch_add(1, Ordering::SeqCst);
+        Self(count)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Default)]
+struct Health {
+    /// 連続して失敗した回数
+    failures: u32,
+    ejected_until: Option<Instant>,
+}
+
+/// 連続して失敗したバックエンドを一定時間外すバックエンドの集まり
+struct Backends<A> {
+    backends: Vec<A>,
+    health: Mutex<Vec<Health>>,
+    in_flight: Vec<AtomicUsize>,
+    max_failures: u32,
+    ejection: Duration,
+}
+
+impl<A> Backends<A>
+where
+    A: AquesTalk,
+{
+    fn new(backends: Vec<A>) -> Self {
+        assert!(!backends.is_empty(), "no backend is specified");
+        let mut this = Self {
+            backends: Vec::new(),
+            health: Mutex::new(Vec::new()),
+            in_flight: Vec::new(),
+            max_failures: 3,
+            ejection: Duration::from_secs(30),
+        };
+        for backend in backends {
+            this.push(backend);
+        }
+        this
+    }
+
+    fn push(&mut self, backend: A) -> usize {
+        self.backends.push(backend);
+        self.health.get_mut().unwrap().push(Health::default());
+        self.in_flight.push(AtomicUsize::new(0));
+        self.backends.len() - 1
+    }
+
+    fn len(&self) -> usize {
+        self.backends.len()
+    }
+
+    fn is_ejected(&self, index: usize) -> bool {
+        let health = self.health.lock().unwrap();
+        health[index]
+            .ejected_until
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    fn report(&self, index: usize, failed: bool) {
+        let mut health = self.health.lock().unwrap();
+        let health = &mut health[index];
+        if !failed {
+            *health = Health::default();
+            return;
+        }
+
+        // 外す期間が過ぎた後に再び失敗した場合は、すぐにまた外す
+        health.failures += 1;
+        if health.failures >= self.max_failures {
+            health.ejected_until = Some(Instant::now() + self.ejection);
+        }
+    }
+
+    /// `order` の順にバックエンドを試し、障害が起きた場合は次のバックエンドを試す。
+    /// 外されているバックエンドは飛ばすが、全て外されている場合は全て試す。
+    fn synthe(&self, order: Vec<usize>, request: &SynthesisRequest) -> Result<A::Wav, ClientError> {
+        let available: Vec<usize> = order
+            .iter()
+            .copied()
+            .filter(|&index| !self.is_ejected(index))
+            .collect();
+        let candidates = if available.is_empty() {
+            order
+        } else {
+            available
+        };
+
+        let mut last_err = None;
+        for index in candidates {
+            let in_flight = InFlight::new(&self.in_flight[index]);
+            let result = self.backends[index].synthe_request(request);
+            drop(in_flight);
+
+            match result {
+                Err(err) if is_backend_failure(&err) => {
+                    self.report(index, true);
+                    last_err = Some(err);
+                }
+                result => {
+                    self.report(index, false);
+                    return result;
+                }
+            }
+        }
+
+        Err(last_err.unwrap())
+    }
+}
+
+macro_rules! impl_ejection {
+    ($type:ident) => {
+        impl<A> $type<A>
+        where
+            A: AquesTalk,
+        {
+            /// `max_failures` 回続けて `Transport`、`Closed`、`Timeout` を返したバックエンドを
+            /// `duration` の間外す (デフォルト: 3 回、30 秒)
+            pub fn with_ejection(mut self, max_failures: u32, duration: Duration) -> Self {
+                self.backends.max_failures = max_failures;
+                self.backends.ejection = duration;
+                self
+            }
+
+            /// 現在外されているバックエンドか
+            pub fn is_ejected(&self, index: usize) -> bool {
+                self.backends.is_ejected(index)
+            }
+        }
+    };
+}
+
+/// 優先順に並べたバックエンドを先頭から試す [`AquesTalk`]
+pub struct Fallback<A> {
+    backends: Backends<A>,
+}
+
+impl<A> Fallback<A>
+where
+    A: AquesTalk,
+{
+    pub fn new(backends: Vec<A>) -> Self {
+        Self {
+            backends: Backends::new(backends),
+        }
+    }
+}
+
+impl_ejection!(Fallback);
+
+impl<A> AquesTalk for Fallback<A>
+where
+    A: AquesTalk,
+{
+    type Wav = A::Wav;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        let order = (0..self.backends.len()).collect();
+        self.backends.synthe(order, request)
+    }
+}
+
+/// 要求ごとに順番にバックエンドを切り替える [`AquesTalk`]
+pub struct RoundRobin<A> {
+    backends: Backends<A>,
+    next: AtomicUsize,
+}
+
+impl<A> RoundRobin<A>
+where
+    A: AquesTalk,
+{
+    pub fn new(backends: Vec<A>) -> Self {
+        Self {
+            backends: Backends::new(backends),
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl_ejection!(RoundRobin);
+
+impl<A> AquesTalk for RoundRobin<A>
+where
+    A: AquesTalk,
+{
+    type Wav = A::Wav;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        let len = self.backends.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % len;
+        let order = (start..len).chain(0..start).collect();
+        self.backends.synthe(order, request)
+    }
+}
+
+/// 処理中の要求が最も少ないバックエンドを選ぶ [`AquesTalk`]
+pub struct LeastBusy<A> {
+    backends: Backends<A>,
+}
+
+impl<A> LeastBusy<A>
+where
+    A: AquesTalk,
+{
+    pub fn new(backends: Vec<A>) -> Self {
+        Self {
+            backends: Backends::new(backends),
+        }
+    }
+}
+
+impl_ejection!(LeastBusy);
+
+impl<A> AquesTalk for LeastBusy<A>
+where
+    A: AquesTalk,
+{
+    type Wav = A::Wav;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        let mut order: Vec<usize> = (0..self.backends.len()).collect();
+        order.sort_by_key(|&index| self.backends.in_flight[index].load(Ordering::SeqCst));
+        self.backends.synthe(order, request)
+    }
+}
+
+/// 声種ごとにバックエンドを振り分ける [`AquesTalk`]
+///
+/// 振り分け先が指定されていない声種には `default` を使う。
+/// 振り分け先で失敗した場合は `default` では合成せず、振り分け先のエラーをそのまま返す。
+///
+/// 声種ごとの振り分け先は 1 つだけなので、失敗したバックエンドを外す仕組みは持たない。
+/// 振り分け先を冗長化する場合は [`Fallback`] などで包んでから渡す。
+pub struct VoiceRouter<A> {
+    backends: Vec<A>,
+    routes: HashMap<String, usize>,
+}
+
+impl<A> VoiceRouter<A>
+where
+    A: AquesTalk,
+{
+    /// `default` は 0 番目のバックエンドになる
+    pub fn new(default: A) -> Self {
+        Self {
+            backends: vec![default],
+            routes: HashMap::new(),
+        }
+    }
+
+    /// `voice_type` の振り分け先を追加する (1 番目から順に番号が付く)
+    pub fn with_route<S: Into<String>>(mut self, voice_type: S, backend: A) -> Self {
+        self.backends.push(backend);
+        self.routes
+            .insert(voice_type.into(), self.backends.len() - 1);
+        self
+    }
+}
+
+impl<A> AquesTalk for VoiceRouter<A>
+where
+    A: AquesTalk,
+{
+    type Wav = A::Wav;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        let index = self.routes.get(&request.voice_type).copied().unwrap_or(0);
+        self.backends[index].synthe_request(request)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::aquestalk::{
+        AquesTalk, Fallback, LeastBusy, RoundRobin, SynthesisRequest, VoiceRouter,
+    };
+    use crate::ClientError;
+
+    /// 自分の名前を返すか `IoError` を返すバックエンド
+    struct Backend {
+        name: &'static str,
+        down: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    impl Backend {
+        fn new(name: &'static str) -> Self {
+            Self {
+                name,
+                down: AtomicBool::new(false),
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl AquesTalk for Backend {
+        type Wav = &'static [u8];
+        fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                return Err(ClientError::Transport {
+                    message: "down".to_string(),
+                });
+            }
+            if request.koe == "PANIC" {
+                panic!("{}", self.name);
+            }
+            if request.koe == "ERR" {
+                return Err(ClientError::AquesTalk {
+                    code: Some(105),
+                    message: String::new(),
+                });
+            }
+            Ok(self.name.as_bytes())
+        }
+    }
+
+    #[test]
+    fn fallback() {
+        let (a, b) = (Backend::new("a"), Backend::new("b"));
+        let aqtk = Fallback::new(vec![&a, &b]).with_ejection(2, Duration::from_millis(50));
+        assert_eq!(aqtk.synthe("f1", "", 100).unwrap(), b"a");
+        // AquestalkError は次を試さない
+        assert!(aqtk.synthe("f1", "ERR", 100).is_err());
+        assert_eq!(b.calls.load(Ordering::SeqCst), 0);
+
+        a.down.store(true, Ordering::SeqCst);
+        for _ in 0..3 {
+            assert_eq!(aqtk.synthe("f1", "", 100).unwrap(), b"b");
+        }
+        assert!(aqtk.is_ejected(0));
+        assert_eq!(a.calls.load(Ordering::SeqCst), 4);
+
+        // 全て外されている場合は全て試す
+        b.down.store(true, Ordering::SeqCst);
+        for _ in 0..3 {
+            assert!(aqtk.synthe("f1", "", 100).is_err());
+        }
+        assert!(aqtk.is_ejected(1));
+        assert_eq!(a.calls.load(Ordering::SeqCst), 5);
+
+        a.down.store(false, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(aqtk.synthe("f1", "", 100).unwrap(), b"a");
+        assert!(!aqtk.is_ejected(0));
+    }
+
+    #[test]
+    fn round_robin() {
+        let (a, b, c) = (Backend::new("a"), Backend::new("b"), Backend::new("c"));
+        let aqtk = RoundRobin::new(vec![&a, &b, &c]).with_ejection(1, Duration::from_secs(60));
+        let names: Vec<_> = (0..4)
+            .map(|_| aqtk.synthe("f1", "", 100).unwrap())
+            .collect();
+        assert_eq!(names, [b"a", b"b", b"c", b"a"]);
+
+        b.down.store(true, Ordering::SeqCst);
+        let names: Vec<_> = (0..4)
+            .map(|_| aqtk.synthe("f1", "", 100).unwrap())
+            .collect();
+        assert_eq!(names, [b"c", b"c", b"a", b"c"]);
+        assert_eq!(b.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn least_busy_panic() {
+        let (a, b) = (Backend::new("a"), Backend::new("b"));
+        let aqtk = LeastBusy::new(vec![&a, &b]);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| aqtk.synthe("f1", "PANIC", 100)));
+        assert!(result.is_err());
+
+        // パニックしたバックエンドも処理中の数に残らない
+        assert_eq!(aqtk.backends.in_flight[0].load(Ordering::SeqCst), 0);
+        assert_eq!(aqtk.synthe("f1", "", 100).unwrap(), b"a");
+    }
+
+    #[test]
+    fn voice_router() {
+        let (a, b) = (Backend::new("a"), Backend::new("b"));
+        let aqtk = VoiceRouter::new(&a).with_route("m1", &b);
+        assert_eq!(aqtk.synthe("f1", "", 100).unwrap(), b"a");
+        assert_eq!(aqtk.synthe("m1", "", 100).unwrap(), b"b");
+
+        // 振り分け先が失敗しても別のバックエンドでは合成しない
+        b.down.store(true, Ordering::SeqCst);
+        for _ in 0..2 {
+            assert!(matches!(
+                aqtk.synthe("m1", "", 100),
+                Err(ClientError::Transport { .. })
+            ));
+        }
+        assert_eq!(aqtk.synthe("f1", "", 100).unwrap(), b"a");
+        assert_eq!(a.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(b.calls.load(Ordering::SeqCst), 3);
+    }
+}
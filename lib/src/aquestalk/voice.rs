@@ -0,0 +1,197 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// 声の性別
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Gender {
+    Female,
+    Male,
+    Neutral,
+}
+
+/// 声種
+///
+/// AquesTalk 標準の声種は表示名などの情報を持つ。
+/// それ以外の文字列は独自のライブラリのフォルダー名として [`Voice::Custom`] になる。
+///
+/// ```
+/// use aquestalk_proxy::aquestalk::{Gender, Voice};
+///
+/// let voice: Voice = "m1".parse().unwrap();
+/// assert_eq!(voice, Voice::M1);
+/// assert_eq!(voice.display_name(), "男性1");
+/// assert_eq!(voice.gender(), Some(Gender::Male));
+///
+/// let voice = Voice::from("yukkuri");
+/// assert_eq!(voice.display_name(), "yukkuri");
+/// assert_eq!(voice.gender(), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Voice {
+    #[default]
+    F1,
+    F2,
+    M1,
+    M2,
+    R1,
+    Dvd,
+    Imd1,
+    Jgr,
+    /// 標準以外の声種
+    Custom(String),
+}
+
+impl Voice {
+    /// AquesTalk 標準の声種
+    pub const STANDARD: [Voice; 8] = [
+        Voice::F1,
+        Voice::F2,
+        Voice::M1,
+        Voice::M2,
+        Voice::R1,
+        Voice::Dvd,
+        Voice::Imd1,
+        Voice::Jgr,
+    ];
+
+    /// 声種の名前 (ライブラリのフォルダー名)
+    pub fn as_str(&self) -> &str {
+        match self {
+            Voice::F1 => "f1",
+            Voice::F2 => "f2",
+            Voice::M1 => "m1",
+            Voice::M2 => "m2",
+            Voice::R1 => "r1",
+            Voice::Dvd => "dvd",
+            Voice::Imd1 => "imd1",
+            Voice::Jgr => "jgr",
+            Voice::Custom(name) => name,
+        }
+    }
+
+    pub fn is_standard(&self) -> bool {
+        !matches!(self, Voice::Custom(_))
+    }
+
+    /// 表示名 (標準以外の声種は名前をそのまま返す)
+    pub fn display_name(&self) -> &str {
+        match self {
+            Voice::F1 => "女性1",
+            Voice::F2 => "女性2",
+            Voice::M1 => "男性1",
+            Voice::M2 => "男性2",
+            Voice::R1 => "ロボット",
+            Voice::Dvd => "機械1",
+            Voice::Imd1 => "中性",
+            Voice::Jgr => "機械2",
+            Voice::Custom(name) => name,
+        }
+    }
+
+    /// 性別 (ロボットや機械の声、標準以外の声種は `None`)
+    pub fn gender(&self) -> Option<Gender> {
+        match self {
+            Voice::F1 | Voice::F2 => Some(Gender::Female),
+            Voice::M1 | Voice::M2 => Some(Gender::Male),
+            Voice::Imd1 => Some(Gender::Neutral),
+            Voice::R1 | Voice::Dvd | Voice::Jgr | Voice::Custom(_) => None,
+        }
+    }
+
+    /// 声の説明 (標準以外の声種は `None`)
+    pub fn description(&self) -> Option<&'static str> {
+        match self {
+            Voice::F1 => Some("標準の女性の声 (ゆっくりボイス)"),
+            Voice::F2 => Some("f1 とは別の声質の女性の声"),
+            Voice::M1 => Some("標準の男性の声"),
+            Voice::M2 => Some("m1 とは別の声質の男性の声"),
+            Voice::R1 => Some("ロボットの声"),
+            Voice::Dvd => Some("機械的な声"),
+            Voice::Imd1 => Some("中性的な声"),
+            Voice::Jgr => Some("dvd とは別の声質の機械的な声"),
+            Voice::Custom(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Voice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AsRef<str> for Voice {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Voice {
+    fn from(name: &str) -> Self {
+        Voice::STANDARD
+            .into_iter()
+            .find(|voice| voice.as_str() == name)
+            .unwrap_or_else(|| Voice::Custom(name.to_string()))
+    }
+}
+
+impl From<String> for Voice {
+    fn from(name: String) -> Self {
+        match Voice::from(name.as_str()) {
+            Voice::Custom(_) => Voice::Custom(name),
+            voice => voice,
+        }
+    }
+}
+
+impl From<Voice> for String {
+    fn from(voice: Voice) -> Self {
+        match voice {
+            Voice::Custom(name) => name,
+            voice => voice.as_str().to_string(),
+        }
+    }
+}
+
+impl FromStr for Voice {
+    type Err = Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Voice::from(s))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Voice;
+
+    #[test]
+    fn round_trip() {
+        for voice in Voice::STANDARD {
+            assert!(voice.is_standard());
+            assert!(voice.description().is_some());
+            assert_eq!(Voice::from(voice.as_str()), voice);
+            assert_eq!(String::from(voice.clone()), voice.to_string());
+        }
+
+        let voice = Voice::from("F1");
+        assert_eq!(voice, Voice::Custom("F1".to_string()));
+        assert_eq!(serde_json::to_string(&voice).unwrap(), r#""F1""#);
+        assert_eq!(
+            serde_json::from_str::<Voice>(r#""dvd""#).unwrap(),
+            Voice::Dvd
+        );
+    }
+}
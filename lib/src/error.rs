@@ -0,0 +1,248 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::error;
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+use crate::aquestalk::SynthesisRequest;
+use crate::messages::{ResponsePayload, SPEED_RANGE};
+
+fn unknown_voice_message(voice_type: &str) -> String {
+    format!("不明な声種 ({})", voice_type)
+}
+
+fn invalid_speed_message(speed: i32) -> String {
+    format!("範囲外の発話速度 ({})", speed)
+}
+
+/// [`AquesTalk`](crate::aquestalk::AquesTalk) の合成が失敗した理由
+///
+/// サーバーから受け取った [`ResponsePayload`] とは相互に変換できる。
+/// `UnknownVoice` と `InvalidSpeed` は専用の項目を持たないため、応答からは送信した要求と合わせて
+/// [`ClientError::from_payload`] で取り出す。
+/// クライアント側で判断する `Transport`、`Closed`、`Timeout`、`NotRecorded` は `IoError` に変換する。
+/// サーバーから受け取った `IoError` は接続の障害ではないため `Server` にする。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientError {
+    /// 接続や送受信に失敗した (`IoError`)
+    Transport { message: String },
+    /// サーバーが要求の処理に失敗した (`IoError`)
+    ///
+    /// 要求が長すぎる場合など、同じ要求を送り直しても同じ結果になる。
+    Server { message: String },
+    /// JSON が不正 (`JsonError`)
+    Protocol { message: String },
+    /// AquesTalk ライブラリ内エラー (`AquestalkError`)
+    AquesTalk { code: Option<i32>, message: String },
+    /// 声種のライブラリが無い (エラーコードの無い `AquestalkError`)
+    UnknownVoice { voice_type: String },
+    /// 発話速度が [`SPEED_RANGE`] の範囲外 (`JsonError`)
+    InvalidSpeed { speed: i32 },
+    /// 接続が既に閉じられている
+    Closed,
+    /// 応答を待ちきれなかった
+    Timeout { message: String },
+    /// [`Replayer`](crate::aquestalk::Replayer) のカセットに一致する記録が無い
+    NotRecorded { message: String },
+}
+
+impl ClientError {
+    /// 要求 `request` に対してサーバーから受け取ったエラーを変換する
+    ///
+    /// デーモンはエラーコードの無い `AquestalkError` を声種が無い場合にだけ返すため、
+    /// その場合は要求の声種を使って `UnknownVoice` にする。
+    /// 範囲外の発話速度を送った要求への `JsonError` は `InvalidSpeed` にする。
+    pub fn from_payload(payload: ResponsePayload, request: &SynthesisRequest) -> Self {
+        match Self::from(payload) {
+            Self::AquesTalk { code: None, .. } => Self::UnknownVoice {
+                voice_type: request.voice_type.clone(),
+            },
+            Self::Protocol { .. } if !SPEED_RANGE.contains(&request.speed) => Self::InvalidSpeed {
+                speed: request.speed,
+            },
+            err => err,
+        }
+    }
+
+    /// 通信の障害によるもので、別の接続やバックエンドでやり直せば成功する可能性がある
+    pub fn is_transport(&self) -> bool {
+        matches!(
+            self,
+            Self::Transport { .. } | Self::Closed | Self::Timeout { .. }
+        )
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport { message } | Self::Server { message } => {
+                write!(f, "IoError: {}", message)
+            }
+            Self::Protocol { message } => write!(f, "JsonError: {}", message),
+            Self::AquesTalk {
+                code: Some(code),
+                message,
+            } => write!(f, "AquestalkError ({}): {}", code, message),
+            Self::AquesTalk {
+                code: None,
+                message,
+            } => write!(f, "AquestalkError: {}", message),
+            Self::UnknownVoice { voice_type } => {
+                write!(f, "AquestalkError: {}", unknown_voice_message(voice_type))
+            }
+            Self::InvalidSpeed { speed } => {
+                write!(f, "JsonError: {}", invalid_speed_message(*speed))
+            }
+            Self::Closed => f.write_str("IoError: Connection is closed."),
+            Self::Timeout { message } => write!(f, "Timeout: {}", message),
+            Self::NotRecorded { message } => write!(f, "NotRecorded: {}", message),
+        }
+    }
+}
+
+impl error::Error for ClientError {}
+
+impl From<ResponsePayload> for ClientError {
+    fn from(payload: ResponsePayload) -> Self {
+        match payload {
+            ResponsePayload::Wav { .. } => Self::Protocol {
+                message: "Unexpected Wav response.".to_string(),
+            },
+            ResponsePayload::AquestalkError { code, message } => Self::AquesTalk { code, message },
+            ResponsePayload::JsonError { message } => Self::Protocol { message },
+            ResponsePayload::IoError { message } => Self::Server { message },
+        }
+    }
+}
+
+impl From<ClientError> for ResponsePayload {
+    fn from(err: ClientError) -> Self {
+        match err {
+            ClientError::Transport { message } | ClientError::Server { message } => {
+                Self::IoError { message }
+            }
+            ClientError::Protocol { message } => Self::JsonError { message },
+            ClientError::AquesTalk { code, message } => Self::AquestalkError { code, message },
+            ClientError::UnknownVoice { voice_type } => Self::AquestalkError {
+                code: None,
+                message: unknown_voice_message(&voice_type),
+            },
+            ClientError::InvalidSpeed { speed } => Self::JsonError {
+                message: invalid_speed_message(speed),
+            },
+            ClientError::Closed => Self::IoError {
+                message: "Connection is closed.".to_string(),
+            },
+            ClientError::Timeout { message } | ClientError::NotRecorded { message } => {
+                Self::IoError { message }
+            }
+        }
+    }
+}
+
+impl From<crate::aquestalk::Error> for ClientError {
+    fn from(err: crate::aquestalk::Error) -> Self {
+        Self::AquesTalk {
+            code: Some(err.code()),
+            message: err.message().to_string(),
+        }
+    }
+}
+
+/// 読み書きのタイムアウトは `Timeout` として区別する
+impl From<io::Error> for ClientError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => Self::Timeout {
+                message: err.to_string(),
+            },
+            _ => Self::Transport {
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(err: serde_json::Error) -> Self {
+        if err.is_io() {
+            io::Error::from(err).into()
+        } else {
+            Self::Protocol {
+                message: err.to_string(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::aquestalk::SynthesisRequest;
+    use crate::messages::ResponsePayload;
+    use crate::ClientError;
+
+    #[test]
+    fn payload() {
+        let errors = [
+            ClientError::Server {
+                message: "x".to_string(),
+            },
+            ClientError::Protocol {
+                message: "x".to_string(),
+            },
+            ClientError::AquesTalk {
+                code: Some(105),
+                message: "x".to_string(),
+            },
+        ];
+        for err in errors {
+            let payload = ResponsePayload::from(err.clone());
+            assert_eq!(ClientError::from(payload), err);
+        }
+
+        // 声種はメッセージからではなく送信した要求から取り出す
+        let request = SynthesisRequest::new("あ").with_voice_type("f3");
+        let err = ClientError::UnknownVoice {
+            voice_type: "f3".to_string(),
+        };
+        let payload = ResponsePayload::from(err.clone());
+        assert!(matches!(
+            ClientError::from(payload.clone()),
+            ClientError::AquesTalk { code: None, .. }
+        ));
+        assert_eq!(ClientError::from_payload(payload, &request), err);
+        assert_eq!(err.to_string(), "AquestalkError: 不明な声種 (f3)");
+        let payload = ResponsePayload::from(crate::aquestalk::Error::new(105));
+        assert!(matches!(
+            ClientError::from_payload(payload, &request),
+            ClientError::AquesTalk {
+                code: Some(105),
+                ..
+            }
+        ));
+
+        let err = ClientError::InvalidSpeed { speed: 400 };
+        let payload = ResponsePayload::from(err.clone());
+        assert!(matches!(payload, ResponsePayload::JsonError { .. }));
+        assert!(matches!(
+            ClientError::from_payload(payload.clone(), &request),
+            ClientError::Protocol { .. }
+        ));
+        assert_eq!(
+            ClientError::from_payload(payload, &request.with_speed(400)),
+            err
+        );
+
+        let payload = ResponsePayload::from(ClientError::Timeout {
+            message: "x".to_string(),
+        });
+        assert!(matches!(payload, ResponsePayload::IoError { .. }));
+    }
+}
@@ -1,4 +1,4 @@
-// Copyright (c) 2021-2022 Na-x4
+// Copyright (c) 2021-2026 Na-x4
 //
 // Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
 // https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
@@ -9,6 +9,14 @@
 pub mod aquestalk;
 pub mod messages;
 
+mod error;
+pub use error::ClientError;
+
 pub mod proxy;
-pub use proxy::stdio::StdioClient;
+#[cfg(feature = "tokio")]
+pub use proxy::asynchronous::{AsyncStdioClient, AsyncTcpClient};
+pub use proxy::retry::RetryPolicy;
+pub use proxy::stdio::{StdioClient, StdioClientPool, Wine, WineError, WineOpener};
 pub use proxy::tcp::TcpClient;
+#[cfg(any(unix, windows))]
+pub use proxy::unix::UnixClient;
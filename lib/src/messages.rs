@@ -1,4 +1,4 @@
-// Copyright (c) 2021-2025 Na-x4
+// Copyright (c) 2021-2026 Na-x4
 //
 // Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
 // https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
@@ -8,6 +8,7 @@
 
 use std::fmt::Display;
 use std::io;
+use std::ops::RangeInclusive;
 
 use base64::prelude::{Engine, BASE64_STANDARD};
 use serde::{Deserialize, Serialize};
@@ -23,14 +24,17 @@ pub struct Request {
     pub koe: String,
 }
 
-fn default_type() -> String {
+pub(crate) fn default_type() -> String {
     "f1".to_string()
 }
 
-fn default_speed() -> i32 {
+pub(crate) fn default_speed() -> i32 {
     100
 }
 
+/// `Request.speed` に指定できる発話速度 [%] の範囲
+pub const SPEED_RANGE: RangeInclusive<i32> = 50..=300;
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
 pub struct Response {
@@ -64,7 +68,7 @@ pub enum ResponseStatus {
     Error,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields, tag = "type")]
 pub enum ResponsePayload {
     Wav {
@@ -1,4 +1,4 @@
-// Copyright (c) 2022 Na-x4
+// Copyright (c) 2022-2026 Na-x4
 //
 // Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
 // https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
@@ -7,11 +7,19 @@
 // except according to those terms.
 
 use std::io::{BufRead, Write};
+use std::time::Duration;
 
-use crate::messages::{Request, Response, ResponsePayload};
+use crate::aquestalk::SynthesisRequest;
+use crate::messages::{Request, Response};
+use crate::ClientError;
 
+#[cfg(feature = "tokio")]
+pub(crate) mod asynchronous;
+pub(crate) mod retry;
 pub(crate) mod stdio;
 pub(crate) mod tcp;
+#[cfg(any(unix, windows))]
+pub(crate) mod unix;
 
 pub struct Client<R, W>
 where
@@ -38,40 +46,72 @@ where
         self.writer.is_none()
     }
 
-    pub fn synthe(
+    pub fn synthe(&mut self, request: &SynthesisRequest) -> Result<Vec<u8>, ClientError> {
+        into_wav(self.send(request)?, request)
+    }
+
+    /// 要求を送信して応答を受け取る。
+    /// 通信に失敗した場合や `willClose` を受け取った場合は以降の要求を送信しない。
+    pub(crate) fn send(&mut self, request: &SynthesisRequest) -> Result<Response, ClientError> {
+        let mut writer = self.writer.take().ok_or(ClientError::Closed)?;
+        let result = self.exchange(&mut writer, request);
+        if keeps_open(&result) {
+            self.writer = Some(writer);
+        }
+        result
+    }
+
+    fn exchange(
         &mut self,
-        voice_type: &str,
-        koe: &str,
-        speed: i32,
-    ) -> Result<Vec<u8>, ResponsePayload> {
-        let mut writer = self.writer.as_mut().ok_or(ResponsePayload::IoError {
-            message: "Writer is already closed.".to_string(),
-        })?;
-
-        serde_json::to_writer(
-            &mut writer,
-            &Request {
-                voice_type: voice_type.into(),
-                koe: koe.into(),
-                speed,
-            },
-        )?;
-        writer.flush().map_err(ResponsePayload::from_io_error)?;
+        writer: &mut W,
+        request: &SynthesisRequest,
+    ) -> Result<Response, ClientError> {
+        writer
+            .write_all(&encode_request(request)?)
+            .map_err(ClientError::from)?;
+        writer.flush().map_err(ClientError::from)?;
 
         let mut response = String::new();
-        self.reader
+        let len = self
+            .reader
             .read_line(&mut response)
-            .map_err(ResponsePayload::from_io_error)?;
-        let response: Response = serde_json::from_str(&response)?;
+            .map_err(ClientError::from)?;
+        decode_response(&response, len)
+    }
+}
 
-        if response.will_close.unwrap_or(false) {
-            drop(self.writer.take().unwrap());
-        }
-        if !response.is_success {
-            return Err(response.response);
-        }
-        let wav = response.response.try_into()?;
+pub(crate) fn encode_request(request: &SynthesisRequest) -> Result<Vec<u8>, ClientError> {
+    Ok(serde_json::to_vec(&Request::from(request))?)
+}
 
-        Ok(wav)
+/// 読み込んだ 1 行を応答として解釈する。`len` が 0 の場合は接続が閉じられている。
+pub(crate) fn decode_response(line: &str, len: usize) -> Result<Response, ClientError> {
+    if len == 0 {
+        return Err(ClientError::Closed);
     }
+
+    Ok(serde_json::from_str(line)?)
+}
+
+/// 応答を受け取った後も同じ接続で要求を送信できるか
+pub(crate) fn keeps_open(result: &Result<Response, ClientError>) -> bool {
+    matches!(result, Ok(response) if !response.will_close.unwrap_or(false))
+}
+
+pub(crate) fn timeout_error(timeout: Duration) -> ClientError {
+    ClientError::Timeout {
+        message: format!("No response within {:?}.", timeout),
+    }
+}
+
+pub(crate) fn into_wav(
+    response: Response,
+    request: &SynthesisRequest,
+) -> Result<Vec<u8>, ClientError> {
+    if !response.is_success {
+        return Err(ClientError::from_payload(response.response, request));
+    }
+    let wav = response.response.try_into()?;
+
+    Ok(wav)
 }
@@ -0,0 +1,162 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{decode_response, encode_request, into_wav, keeps_open};
+use crate::aquestalk::SynthesisRequest;
+use crate::messages::Response;
+use crate::ClientError;
+
+mod stdio;
+pub use stdio::AsyncStdioClient;
+
+mod tcp;
+pub use tcp::AsyncTcpClient;
+
+/// [`super::Client`] の非同期版
+pub struct AsyncClient<R, W>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    reader: R,
+    writer: Option<W>,
+}
+
+impl<R, W> AsyncClient<R, W>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    pub fn new(reader: R, writer: W) -> Self {
+        AsyncClient {
+            reader,
+            writer: Some(writer),
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.writer.is_none()
+    }
+
+    pub async fn synthe(&mut self, request: &SynthesisRequest) -> Result<Vec<u8>, ClientError> {
+        into_wav(self.send(request).await?, request)
+    }
+
+    /// [`super::Client::send`] の非同期版
+    ///
+    /// 応答を受け取るまでは閉じた状態にしておくため、
+    /// 途中で `Future` が破棄された場合も読み残した応答を次の要求で受け取ることはない。
+    pub(crate) async fn send(
+        &mut self,
+        request: &SynthesisRequest,
+    ) -> Result<Response, ClientError> {
+        let mut writer = self.writer.take().ok_or(ClientError::Closed)?;
+        let result = self.exchange(&mut writer, request).await;
+        if keeps_open(&result) {
+            self.writer = Some(writer);
+        }
+        result
+    }
+
+    async fn exchange(
+        &mut self,
+        writer: &mut W,
+        request: &SynthesisRequest,
+    ) -> Result<Response, ClientError> {
+        writer
+            .write_all(&encode_request(request)?)
+            .await
+            .map_err(ClientError::from)?;
+        writer.flush().await.map_err(ClientError::from)?;
+
+        let mut response = String::new();
+        let len = self
+            .reader
+            .read_line(&mut response)
+            .await
+            .map_err(ClientError::from)?;
+        decode_response(&response, len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    use super::AsyncClient;
+    use crate::aquestalk::SynthesisRequest;
+    use crate::ClientError;
+
+    #[tokio::test]
+    async fn will_close() {
+        let (client, mut server) = duplex(1024);
+        let (reader, writer) = tokio::io::split(client);
+        let mut client = AsyncClient::new(BufReader::new(reader), writer);
+
+        server
+            .write_all(
+                b"{\"isSuccess\":true,\"response\":{\"type\":\"Wav\",\"wav\":\"UklGRg==\"}}\n\
+                  {\"isSuccess\":false,\"response\":{\"type\":\"JsonError\",\"message\":\"x\"},\"willClose\":true}\n",
+            )
+            .await
+            .unwrap();
+
+        let request = |koe| SynthesisRequest::new(koe);
+        assert_eq!(client.synthe(&request("あ")).await.unwrap(), b"RIFF");
+        assert!(!client.is_closed());
+        assert!(client.synthe(&request("い")).await.is_err());
+        assert!(client.is_closed());
+        assert!(client.synthe(&request("う")).await.is_err());
+
+        let mut requests = vec![0; 1024];
+        let len = server.read(&mut requests).await.unwrap();
+        let requests = String::from_utf8(requests[..len].to_vec()).unwrap();
+        assert!(requests.contains("\"koe\":\"あ\""));
+        assert!(!requests.contains("\"koe\":\"う\""));
+    }
+
+    #[tokio::test]
+    async fn closed() {
+        let (client, mut server) = duplex(1024);
+        let (reader, writer) = tokio::io::split(client);
+        let mut client = AsyncClient::new(BufReader::new(reader), writer);
+        server.shutdown().await.unwrap();
+
+        let result = client.synthe(&SynthesisRequest::new("あ")).await;
+        assert!(matches!(result, Err(ClientError::Closed)));
+        assert!(client.is_closed());
+        let result = client.synthe(&SynthesisRequest::new("い")).await;
+        assert!(matches!(result, Err(ClientError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn cancelled() {
+        let (client, mut server) = duplex(1024);
+        let (reader, writer) = tokio::io::split(client);
+        let mut client = AsyncClient::new(BufReader::new(reader), writer);
+
+        let request = SynthesisRequest::new("あ");
+        let result = tokio::time::timeout(Duration::from_millis(10), client.synthe(&request)).await;
+        assert!(result.is_err());
+        assert!(client.is_closed());
+
+        // 破棄された要求への応答を次の要求の応答として受け取らない
+        server
+            .write_all(
+                b"{\"isSuccess\":true,\"response\":{\"type\":\"Wav\",\"wav\":\"UklGRg==\"}}\n",
+            )
+            .await
+            .unwrap();
+        let result = client.synthe(&request).await;
+        assert!(matches!(result, Err(ClientError::Closed)));
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::ffi::OsStr;
+use std::io;
+use std::process::Stdio as StdioEnum;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::BufReader;
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+use crate::aquestalk::{AsyncAquesTalk, SynthesisRequest};
+use crate::proxy::timeout_error;
+use crate::ClientError;
+
+type Client = super::AsyncClient<BufReader<ChildStdout>, ChildStdin>;
+
+struct AsyncStdioClientImpl {
+    command: Child,
+    client: Client,
+}
+
+impl AsyncStdioClientImpl {
+    fn has_exited(&mut self) -> bool {
+        match self.command.try_wait() {
+            Ok(None) => (),
+            _ => return true,
+        }
+
+        if self.client.is_closed() {
+            return true;
+        }
+
+        false
+    }
+}
+
+/// [`crate::StdioClient`] の非同期版
+pub struct AsyncStdioClient<S, F> {
+    program: S,
+    opener: F,
+    timeout: Option<Duration>,
+    inner: Arc<Mutex<Option<AsyncStdioClientImpl>>>,
+}
+
+impl<S, F> AsyncStdioClient<S, F>
+where
+    S: AsRef<OsStr> + Sync,
+    F: Fn(&mut Command) -> &mut Command + Sync,
+{
+    pub fn new(program: S, opener: F) -> Self {
+        Self {
+            program,
+            opener,
+            timeout: None,
+            inner: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// [`crate::StdioClient::with_timeout`] を参照
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn open(&self) -> io::Result<AsyncStdioClientImpl> {
+        // 応答を待っている途中で破棄された場合に子プロセスが残らないようにする
+        let mut command = (self.opener)(&mut Command::new(&self.program))
+            .stdin(StdioEnum::piped())
+            .stdout(StdioEnum::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let reader = BufReader::new(command.stdout.take().unwrap());
+        let writer = command.stdin.take().unwrap();
+
+        let client = Client::new(reader, writer);
+
+        Ok(AsyncStdioClientImpl { command, client })
+    }
+}
+
+impl<S, F> AsyncAquesTalk for AsyncStdioClient<S, F>
+where
+    S: AsRef<OsStr> + Sync,
+    F: Fn(&mut Command) -> &mut Command + Sync,
+{
+    type Wav = Vec<u8>;
+    async fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        let mut inner = self.inner.lock().await;
+        if inner.is_none() || inner.as_mut().unwrap().has_exited() {
+            // 前回の要求が途中で破棄された場合は、応答を読み残した子プロセスを終了させる
+            *inner = None;
+            *inner = Some(self.open().map_err(ClientError::from)?);
+        }
+
+        let opened = inner.as_mut().unwrap();
+        let Some(timeout) = self.timeout else {
+            return opened.client.synthe(request).await;
+        };
+        match tokio::time::timeout(timeout, opened.client.synthe(request)).await {
+            Ok(result) => result,
+            Err(_) => {
+                *inner = None;
+                Err(timeout_error(timeout))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::AsyncStdioClient;
+    use crate::aquestalk::{AsyncAquesTalk, SynthesisRequest};
+
+    // 要求は改行で終わらないため `}` までを 1 つの要求として読む
+    const RESPONDER: &str = "while read -r -d '}' line; do sleep 0.2; \
+        echo '{\"isSuccess\":true,\"response\":{\"type\":\"Wav\",\"wav\":\"UklGRg==\"}}'; done";
+
+    #[tokio::test]
+    async fn cancelled() {
+        let spawned = AtomicUsize::new(0);
+        let aqtk = AsyncStdioClient::new("bash", |c| {
+            spawned.fetch_add(1, Ordering::SeqCst);
+            c.arg("-c").arg(RESPONDER)
+        });
+        let request = SynthesisRequest::new("あ");
+
+        let result =
+            tokio::time::timeout(Duration::from_millis(50), aqtk.synthe_request(&request)).await;
+        assert!(result.is_err());
+
+        // 応答を読み残した子プロセスは使わずに起動し直す
+        assert_eq!(aqtk.synthe_request(&request).await.unwrap(), b"RIFF");
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+        assert_eq!(aqtk.synthe_request(&request).await.unwrap(), b"RIFF");
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::io::BufReader;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+use crate::aquestalk::{AsyncAquesTalk, SynthesisRequest};
+use crate::messages::Response;
+use crate::proxy::{into_wav, timeout_error};
+use crate::ClientError;
+
+use super::AsyncClient;
+
+type Client = AsyncClient<BufReader<OwnedReadHalf>, OwnedWriteHalf>;
+
+/// 接続を使い回す非同期の TCP クライアント
+///
+/// [`TcpClient`](crate::TcpClient) と同様に使用中でない接続をプールに保持する。
+/// 複数のアドレスや再試行には対応しない。
+///
+/// タイムアウトした場合は [`ClientError::Timeout`] を返し、その接続は破棄する。
+pub struct AsyncTcpClient<A> {
+    addr: A,
+    timeout: Option<Duration>,
+    pool: Mutex<Vec<Client>>,
+}
+
+impl<A> AsyncTcpClient<A>
+where
+    A: ToSocketAddrs + Send + Sync,
+{
+    pub fn new(addr: A) -> Self {
+        Self {
+            addr,
+            timeout: None,
+            pool: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 接続から応答を受け取るまでの時間の上限
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    async fn connect(&self) -> Result<Client, ClientError> {
+        let stream = TcpStream::connect(&self.addr)
+            .await
+            .map_err(ClientError::from)?;
+        let (reader, writer) = stream.into_split();
+        Ok(AsyncClient::new(BufReader::new(reader), writer))
+    }
+
+    async fn exchange(
+        &self,
+        request: &SynthesisRequest,
+    ) -> Result<(Client, Result<Response, ClientError>), ClientError> {
+        let pooled = self.pool.lock().unwrap().pop();
+        if let Some(mut client) = pooled {
+            match client.send(request).await {
+                // サーバー側のタイムアウトなどで閉じられている場合は新しい接続でやり直す
+                Err(ClientError::Transport { .. } | ClientError::Closed) => (),
+                result => return Ok((client, result)),
+            }
+        }
+
+        let mut client = self.connect().await?;
+        let result = client.send(request).await;
+        Ok((client, result))
+    }
+
+    /// 途中で `Future` が破棄された場合、使用中の接続はプールに戻さずに破棄される
+    async fn send(&self, request: &SynthesisRequest) -> Result<Vec<u8>, ClientError> {
+        let (client, result) = self.exchange(request).await?;
+        if !client.is_closed() {
+            self.pool.lock().unwrap().push(client);
+        }
+        into_wav(result?, request)
+    }
+}
+
+impl<A> AsyncAquesTalk for AsyncTcpClient<A>
+where
+    A: ToSocketAddrs + Send + Sync,
+{
+    type Wav = Vec<u8>;
+    async fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.send(request))
+                .await
+                .unwrap_or_else(|_| Err(timeout_error(timeout))),
+            None => self.send(request).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use serde_json::{Deserializer, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use crate::aquestalk::AsyncAquesTalk;
+    use crate::AsyncTcpClient;
+    use crate::ClientError;
+
+    #[tokio::test]
+    async fn tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while Deserializer::from_slice(&request)
+                .into_iter::<Value>()
+                .next()
+                .is_none_or(|request| request.is_err())
+            {
+                let mut buf = [0; 256];
+                let len = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..len]);
+            }
+            let request: Value = serde_json::from_slice(&request).unwrap();
+            assert_eq!(request["koe"], "こんにちわ、せ'かい");
+            stream
+                .write_all(
+                    b"{\"isSuccess\":true,\"response\":{\"type\":\"Wav\",\"wav\":\"UklGRg==\"}}\n",
+                )
+                .await
+                .unwrap();
+        });
+
+        let aqtk = AsyncTcpClient::new(addr);
+        assert_eq!(
+            aqtk.synthe("f1", "こんにちわ、せ'かい", 100).await.unwrap(),
+            b"RIFF"
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn pool() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // 1 つの接続だけを受け付け、2 つの要求に応答する
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            for _ in 0..2 {
+                let mut request = Vec::new();
+                while Deserializer::from_slice(&request)
+                    .into_iter::<Value>()
+                    .next()
+                    .is_none_or(|request| request.is_err())
+                {
+                    let mut buf = [0; 256];
+                    let len = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..len]);
+                }
+                stream
+                    .write_all(
+                        b"{\"isSuccess\":true,\"response\":{\"type\":\"Wav\",\"wav\":\"UklGRg==\"}}\n",
+                    )
+                    .await
+                    .unwrap();
+            }
+            listener
+        });
+
+        // 新しく接続した場合は応答が無いためタイムアウトする
+        let aqtk = AsyncTcpClient::new(addr).with_timeout(Duration::from_secs(1));
+        for _ in 0..2 {
+            assert_eq!(aqtk.synthe("f1", "あ", 100).await.unwrap(), b"RIFF");
+        }
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        // 接続を受け付けるが応答しないサーバー
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move { listener.accept().await.unwrap() });
+
+        let aqtk = AsyncTcpClient::new(addr).with_timeout(Duration::from_millis(100));
+        assert!(matches!(
+            aqtk.synthe("f1", "あ", 100).await,
+            Err(ClientError::Timeout { .. })
+        ));
+        server.await.unwrap();
+    }
+}
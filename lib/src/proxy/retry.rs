@@ -0,0 +1,114 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::time::Duration;
+
+use crate::ClientError;
+
+/// 失敗した要求を再試行する方針
+///
+/// `AquesTalk`、`UnknownVoice`、`InvalidSpeed`、`Server`、`NotRecorded` は同じ要求で必ず同じ結果になるため、設定に関わらず再試行しない。
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 最初の試行を含めた最大試行回数
+    pub max_attempts: u32,
+    /// 最初の再試行までの待ち時間
+    pub initial_backoff: Duration,
+    /// 待ち時間の上限
+    pub max_backoff: Duration,
+    /// 再試行ごとに待ち時間に掛ける倍率
+    pub multiplier: u32,
+    /// `Transport` と `Closed` を再試行するか
+    pub retry_io_error: bool,
+    /// `Protocol` を再試行するか
+    pub retry_json_error: bool,
+    /// `Timeout` を再試行するか
+    pub retry_timeout_error: bool,
+}
+
+impl RetryPolicy {
+    /// 再試行しない
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn is_retryable(&self, err: &ClientError) -> bool {
+        match err {
+            ClientError::AquesTalk { .. }
+            | ClientError::UnknownVoice { .. }
+            | ClientError::InvalidSpeed { .. }
+            | ClientError::Server { .. }
+            | ClientError::NotRecorded { .. } => false,
+            ClientError::Protocol { .. } => self.retry_json_error,
+            ClientError::Transport { .. } | ClientError::Closed => self.retry_io_error,
+            ClientError::Timeout { .. } => self.retry_timeout_error,
+        }
+    }
+
+    /// `retry` 回目 (0 始まり) の再試行までの待ち時間
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        self.multiplier
+            .checked_pow(retry)
+            .and_then(|multiplier| self.initial_backoff.checked_mul(multiplier))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2,
+            retry_io_error: true,
+            retry_json_error: false,
+            retry_timeout_error: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::ClientError;
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(5), Duration::from_secs(2));
+        assert_eq!(policy.backoff(100), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn retryable() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable(&ClientError::Closed));
+        assert!(!policy.is_retryable(&ClientError::Protocol {
+            message: String::new()
+        }));
+        assert!(!policy.is_retryable(&ClientError::Timeout {
+            message: String::new()
+        }));
+        assert!(!policy.is_retryable(&ClientError::Server {
+            message: String::new()
+        }));
+        assert!(!policy.is_retryable(&ClientError::AquesTalk {
+            code: Some(105),
+            message: String::new()
+        }));
+    }
+}
@@ -1,4 +1,4 @@
-// Copyright (c) 2022-2025 Na-x4
+// Copyright (c) 2022-2026 Na-x4
 //
 // Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
 // https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
@@ -9,13 +9,41 @@
 use std::ffi::OsStr;
 use std::io::{self, BufReader};
 use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio as StdioEnum};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
-use crate::aquestalk::AquesTalk;
-use crate::messages::ResponsePayload;
+use crate::aquestalk::{AquesTalk, SynthesisRequest};
+use crate::ClientError;
+
+use super::timeout_error;
+
+mod pool;
+pub use pool::StdioClientPool;
+
+mod wine;
+pub use wine::{Wine, WineError, WineOpener};
 
 type Client = super::Client<BufReader<ChildStdout>, ChildStdin>;
 
+fn spawn<S, F>(program: &S, opener: &F) -> io::Result<StdioClientImpl>
+where
+    S: AsRef<OsStr>,
+    F: Fn(&mut Command) -> &mut Command,
+{
+    let mut command = opener(&mut Command::new(program))
+        .stdin(StdioEnum::piped())
+        .stdout(StdioEnum::piped())
+        .spawn()?;
+    let reader = BufReader::new(command.stdout.take().unwrap());
+    let writer = command.stdin.take().unwrap();
+
+    let client = Client::new(reader, writer);
+
+    Ok(StdioClientImpl { command, client })
+}
+
 struct StdioClientImpl {
     command: Child,
     client: Client,
@@ -34,11 +62,50 @@ impl StdioClientImpl {
 
         false
     }
+
+    /// `timeout` までに応答が無ければ子プロセスを終了させて `Timeout` を返す
+    fn synthe(
+        &mut self,
+        timeout: Option<Duration>,
+        request: &SynthesisRequest,
+    ) -> Result<Vec<u8>, ClientError> {
+        let Some(timeout) = timeout else {
+            return self.client.synthe(request);
+        };
+
+        let Self { command, client } = self;
+        let (done, finished) = mpsc::channel::<()>();
+        thread::scope(|s| {
+            let watchdog = s.spawn(move || {
+                if finished.recv_timeout(timeout) != Err(RecvTimeoutError::Timeout) {
+                    return false;
+                }
+                let _ = command.kill();
+                let _ = command.wait();
+                true
+            });
+
+            let result = client.synthe(request);
+            drop(done);
+            match (result, watchdog.join().unwrap()) {
+                (Err(_), true) => Err(timeout_error(timeout)),
+                (result, _) => result,
+            }
+        })
+    }
+}
+
+impl Drop for StdioClientImpl {
+    fn drop(&mut self) {
+        let _ = self.command.kill();
+        let _ = self.command.wait();
+    }
 }
 
 pub struct StdioClient<S, F> {
     program: S,
     opener: F,
+    timeout: Option<Duration>,
     inner: Arc<Mutex<Option<StdioClientImpl>>>,
 }
 
@@ -51,21 +118,21 @@ where
         Self {
             program,
             opener,
+            timeout: None,
             inner: Arc::new(Mutex::new(None)),
         }
     }
 
-    fn open(&self) -> io::Result<StdioClientImpl> {
-        let mut command = (self.opener)(&mut Command::new(&self.program))
-            .stdin(StdioEnum::piped())
-            .stdout(StdioEnum::piped())
-            .spawn()?;
-        let reader = BufReader::new(command.stdout.take().unwrap());
-        let writer = command.stdin.take().unwrap();
-
-        let client = Client::new(reader, writer);
+    /// 1 回の要求で応答を待つ時間の上限
+    ///
+    /// 応答が無い子プロセスは終了させ、次の要求で起動し直す。
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
 
-        Ok(StdioClientImpl { command, client })
+    fn open(&self) -> io::Result<StdioClientImpl> {
+        spawn(&self.program, &self.opener)
     }
 }
 
@@ -75,19 +142,13 @@ where
     F: Fn(&mut Command) -> &mut Command,
 {
     type Wav = Vec<u8>;
-    fn synthe(
-        &self,
-        voice_type: &str,
-        koe: &str,
-        speed: i32,
-    ) -> Result<Self::Wav, crate::messages::ResponsePayload> {
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
         let mut inner = self.inner.lock().unwrap();
         if inner.is_none() || inner.as_mut().unwrap().has_exited() {
-            *inner = Some(self.open().map_err(ResponsePayload::from_io_error)?);
+            *inner = Some(self.open().map_err(ClientError::from)?);
         }
 
-        let client = &mut inner.as_mut().unwrap().client;
-        client.synthe(voice_type, koe, speed)
+        inner.as_mut().unwrap().synthe(self.timeout, request)
     }
 }
 
@@ -96,8 +157,14 @@ mod test {
     use std::fs;
     use std::io::ErrorKind;
     use std::path::Path;
+    #[cfg(unix)]
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    #[cfg(unix)]
+    use std::time::Duration;
 
     use crate::aquestalk::AquesTalk;
+    #[cfg(unix)]
+    use crate::ClientError;
     use crate::StdioClient;
 
     #[cfg_attr(not(windows), ignore)]
@@ -116,4 +183,23 @@ mod test {
         aqtk.synthe("f1", "こんにちわ、せ'かい", 100).unwrap();
         aqtk.synthe("f1", "ゆっくりしていってね", 100).unwrap();
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn timeout() {
+        let spawned = AtomicUsize::new(0);
+        let aqtk = StdioClient::new("sleep", |c| {
+            spawned.fetch_add(1, Ordering::SeqCst);
+            c.arg("10")
+        })
+        .with_timeout(Duration::from_millis(100));
+
+        for _ in 0..2 {
+            assert!(matches!(
+                aqtk.synthe("f1", "あ", 100),
+                Err(ClientError::Timeout { .. })
+            ));
+        }
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+    }
 }
@@ -0,0 +1,299 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::ffi::OsStr;
+use std::io;
+use std::process::Command;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::aquestalk::{AquesTalk, SynthesisRequest};
+use crate::ClientError;
+
+use super::{spawn, StdioClientImpl};
+
+struct Pool {
+    /// 待機中の子プロセス (古い順)
+    idle: Vec<(Instant, StdioClientImpl)>,
+    /// 待機中と使用中を合わせた子プロセスの数
+    size: usize,
+}
+
+/// 複数の子プロセスに要求を振り分ける [`super::StdioClient`]
+///
+/// 子プロセスは最初の要求で `min` 個起動し、その後は必要になった時点で `max` 個まで起動する。
+/// `idle_timeout` より長く使われなかった子プロセスは `min` 個になるまで終了させる。
+pub struct StdioClientPool<S, F> {
+    program: S,
+    opener: F,
+    min: usize,
+    max: usize,
+    idle_timeout: Duration,
+    timeout: Option<Duration>,
+    pool: Mutex<Pool>,
+    released: Condvar,
+}
+
+impl<S, F> StdioClientPool<S, F>
+where
+    S: AsRef<OsStr>,
+    F: Fn(&mut Command) -> &mut Command,
+{
+    pub fn new(program: S, opener: F, min: usize, max: usize) -> Self {
+        assert!(
+            0 < max && min <= max,
+            "invalid pool size ({}..={})",
+            min,
+            max
+        );
+        Self {
+            program,
+            opener,
+            min,
+            max,
+            idle_timeout: Duration::from_secs(60),
+            timeout: None,
+            pool: Mutex::new(Pool {
+                idle: Vec::new(),
+                size: 0,
+            }),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// 1 回の要求で応答を待つ時間の上限 ([`super::StdioClient::with_timeout`] を参照)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// 待機中または起動中の子プロセスの数
+    pub fn size(&self) -> usize {
+        self.pool.lock().unwrap().size
+    }
+
+    /// 子プロセスが `min` 個になるまで起動して待機させる
+    ///
+    /// 最初の要求を待たずに起動しておく場合に呼ぶ。
+    pub fn fill(&self) -> io::Result<()> {
+        loop {
+            let mut pool = self.pool.lock().unwrap();
+            if pool.size >= self.min {
+                return Ok(());
+            }
+            pool.size += 1;
+            drop(pool);
+
+            let mut lease = Lease {
+                pool: self,
+                inner: None,
+            };
+            lease.inner = Some(spawn(&self.program, &self.opener)?);
+        }
+    }
+
+    /// `idle_timeout` より長く待機している子プロセスを `min` 個になるまで終了させる
+    fn reap(&self, pool: &mut Pool) {
+        let now = Instant::now();
+        while pool.size > self.min
+            && pool
+                .idle
+                .first()
+                .is_some_and(|(used, _)| now.duration_since(*used) > self.idle_timeout)
+        {
+            pool.idle.remove(0);
+            pool.size -= 1;
+        }
+    }
+
+    fn acquire(&self) -> io::Result<Lease<'_, S, F>> {
+        let mut pool = self.pool.lock().unwrap();
+        self.reap(&mut pool);
+        loop {
+            while let Some((_, mut inner)) = pool.idle.pop() {
+                if !inner.has_exited() {
+                    return Ok(Lease {
+                        pool: self,
+                        inner: Some(inner),
+                    });
+                }
+                pool.size -= 1;
+            }
+
+            if pool.size < self.max {
+                pool.size += 1;
+                drop(pool);
+                // 起動に失敗した場合は `Lease` が `size` を戻す
+                let mut lease = Lease {
+                    pool: self,
+                    inner: None,
+                };
+                lease.inner = Some(spawn(&self.program, &self.opener)?);
+                return Ok(lease);
+            }
+
+            pool = self.released.wait(pool).unwrap();
+        }
+    }
+
+    /// 子プロセスをプールに戻す。
+    /// 終了している場合や起動できなかった場合 (`None`)、要求の途中でパニックした場合は数から除く。
+    fn release(&self, inner: Option<StdioClientImpl>) {
+        let inner = inner
+            .filter(|_| !thread::panicking())
+            .and_then(|mut inner| (!inner.has_exited()).then_some(inner));
+        let mut pool = self.pool.lock().unwrap();
+        match inner {
+            Some(inner) => pool.idle.push((Instant::now(), inner)),
+            None => pool.size -= 1,
+        }
+        self.reap(&mut pool);
+
+        self.released.notify_one();
+    }
+}
+
+/// プールから取り出した子プロセス。破棄するとプールに戻す。
+struct Lease<'a, S, F>
+where
+    S: AsRef<OsStr>,
+    F: Fn(&mut Command) -> &mut Command,
+{
+    pool: &'a StdioClientPool<S, F>,
+    inner: Option<StdioClientImpl>,
+}
+
+impl<S, F> Drop for Lease<'_, S, F>
+where
+    S: AsRef<OsStr>,
+    F: Fn(&mut Command) -> &mut Command,
+{
+    fn drop(&mut self) {
+        self.pool.release(self.inner.take());
+    }
+}
+
+impl<S, F> AquesTalk for StdioClientPool<S, F>
+where
+    S: AsRef<OsStr>,
+    F: Fn(&mut Command) -> &mut Command,
+{
+    type Wav = Vec<u8>;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        self.fill().map_err(ClientError::from)?;
+        let mut lease = self.acquire().map_err(ClientError::from)?;
+        lease.inner.as_mut().unwrap().synthe(self.timeout, request)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::process::Command;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::aquestalk::AquesTalk;
+    use crate::messages::Request;
+    use crate::StdioClientPool;
+
+    /// 固定長の要求を読むたびに少し待ってから応答する子プロセスのプール
+    fn pool(
+        spawned: Arc<AtomicUsize>,
+        min: usize,
+        max: usize,
+        idle_timeout: Duration,
+    ) -> StdioClientPool<&'static str, impl Fn(&mut Command) -> &mut Command + Sync> {
+        let len = serde_json::to_vec(&Request {
+            voice_type: "f1".to_string(),
+            speed: 100,
+            koe: "あ".to_string(),
+        })
+        .unwrap()
+        .len();
+        let script = format!(
+            "while [ -n \"$(head -c {})\" ]; do sleep 0.05; echo '{}'; done",
+            len, r#"{"isSuccess":true,"response":{"type":"Wav","wav":"UklGRg=="}}"#
+        );
+
+        StdioClientPool::new(
+            "sh",
+            move |c| {
+                spawned.fetch_add(1, Ordering::SeqCst);
+                c.arg("-c").arg(&script)
+            },
+            min,
+            max,
+        )
+        .with_idle_timeout(idle_timeout)
+    }
+
+    #[test]
+    fn pool_size() {
+        let spawned = Arc::new(AtomicUsize::new(0));
+        let aqtk = pool(Arc::clone(&spawned), 1, 2, Duration::from_secs(60));
+
+        for _ in 0..3 {
+            assert_eq!(aqtk.synthe("f1", "あ", 100).unwrap(), b"RIFF");
+        }
+        assert_eq!(spawned.load(Ordering::SeqCst), 1);
+
+        let barrier = Barrier::new(4);
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    barrier.wait();
+                    assert_eq!(aqtk.synthe("f1", "あ", 100).unwrap(), b"RIFF");
+                });
+            }
+        });
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn fill() {
+        let spawned = Arc::new(AtomicUsize::new(0));
+        let aqtk = pool(Arc::clone(&spawned), 2, 3, Duration::from_secs(60));
+        aqtk.fill().unwrap();
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+        assert_eq!(aqtk.size(), 2);
+
+        assert_eq!(aqtk.synthe("f1", "あ", 100).unwrap(), b"RIFF");
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn panic() {
+        let aqtk = StdioClientPool::new("true", |_| panic!(), 0, 1);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| aqtk.synthe("f1", "あ", 100)));
+        assert!(result.is_err());
+        assert_eq!(aqtk.size(), 0);
+    }
+
+    #[test]
+    fn shrink() {
+        let spawned = Arc::new(AtomicUsize::new(0));
+        let aqtk = StdioClientPool::new("false", |c| c, 0, 1);
+        assert!(aqtk.synthe("f1", "あ", 100).is_err());
+        assert_eq!(aqtk.size(), 0);
+
+        let aqtk = pool(Arc::clone(&spawned), 0, 1, Duration::from_millis(1));
+        assert_eq!(aqtk.synthe("f1", "あ", 100).unwrap(), b"RIFF");
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(aqtk.synthe("f1", "あ", 100).unwrap(), b"RIFF");
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::env;
+use std::error;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{self, Path, PathBuf};
+use std::process::{Command, Stdio as StdioEnum};
+
+use super::{StdioClient, StdioClientPool};
+
+/// [`Wine`] で起動する子プロセスの設定
+pub type WineOpener = Box<dyn Fn(&mut Command) -> &mut Command + Send + Sync>;
+
+#[derive(Debug)]
+pub enum WineError {
+    /// wine が見つからない
+    WineNotFound,
+    /// `aquestalk-proxyd.exe` が見つからない
+    ExeNotFound(PathBuf),
+    /// `<声種>/AquesTalk.dll` が 1 つも見つからない
+    LibraryNotFound(PathBuf),
+    /// 32 ビットの Windows プログラムを実行できない
+    Wine32Unavailable(String),
+    /// デーモンが起動に失敗した (標準エラー出力)
+    Startup(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for WineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WineNotFound => {
+                write!(
+                    f,
+                    "wine is not found. Install wine or set WINE environment variable."
+                )
+            }
+            Self::ExeNotFound(path) => write!(f, "{} is not found.", path.display()),
+            Self::LibraryNotFound(path) => {
+                write!(f, "AquesTalk.dll is not found in {}.", path.display())
+            }
+            Self::Wine32Unavailable(stderr) => write!(
+                f,
+                "wine cannot run 32-bit programs. Install 32-bit wine (wine32).\n{}",
+                stderr
+            ),
+            Self::Startup(stderr) => write!(f, "aquestalk-proxyd failed to start.\n{}", stderr),
+            Self::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for WineError {}
+
+impl From<io::Error> for WineError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Wine で Windows 版の `aquestalk-proxyd.exe` を起動する設定
+#[derive(Debug, Clone)]
+pub struct Wine {
+    wine: PathBuf,
+    prefix: Option<PathBuf>,
+    exe: PathBuf,
+    lib_path: PathBuf,
+}
+
+impl Wine {
+    /// 環境変数 `WINE` で指定された wine か、`PATH` から見つけた wine を使う
+    pub fn new<E, P>(exe: E, lib_path: P) -> Result<Self, WineError>
+    where
+        E: Into<PathBuf>,
+        P: Into<PathBuf>,
+    {
+        let wine = find_wine().ok_or(WineError::WineNotFound)?;
+        Ok(Self::with_wine(wine, exe, lib_path))
+    }
+
+    pub fn with_wine<W, E, P>(wine: W, exe: E, lib_path: P) -> Self
+    where
+        W: Into<PathBuf>,
+        E: Into<PathBuf>,
+        P: Into<PathBuf>,
+    {
+        Self {
+            wine: wine.into(),
+            prefix: env::var_os("WINEPREFIX").map(PathBuf::from),
+            exe: exe.into(),
+            lib_path: lib_path.into(),
+        }
+    }
+
+    /// `WINEPREFIX` (デフォルト: 環境変数 `WINEPREFIX`)
+    pub fn with_prefix<P: Into<PathBuf>>(mut self, prefix: P) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn program(&self) -> &Path {
+        &self.wine
+    }
+
+    /// `wine aquestalk-proxyd.exe --path=<lib_path> <mode>` を起動するように設定する
+    pub fn configure<'a, S>(&self, command: &'a mut Command, mode: &[S]) -> &'a mut Command
+    where
+        S: AsRef<OsStr>,
+    {
+        if let Some(prefix) = &self.prefix {
+            command.env("WINEPREFIX", prefix);
+        }
+        if env::var_os("WINEDEBUG").is_none() {
+            command.env("WINEDEBUG", "fixme-all");
+        }
+
+        let mut path_arg = OsString::from("--path=");
+        path_arg.push(windows_path(&self.lib_path));
+        command
+            .arg(windows_path(&self.exe))
+            .arg(path_arg)
+            .args(mode)
+    }
+
+    pub fn command<S: AsRef<OsStr>>(&self, mode: &[S]) -> Command {
+        let mut command = Command::new(&self.wine);
+        self.configure(&mut command, mode);
+        command
+    }
+
+    /// 標準入力を閉じた状態で標準入出力モードを起動し、すぐに正常終了するか確かめる
+    pub fn check(&self) -> Result<(), WineError> {
+        if !self.exe.is_file() {
+            return Err(WineError::ExeNotFound(self.exe.clone()));
+        }
+        let has_library = fs::read_dir(&self.lib_path)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .any(|entry| entry.path().join("AquesTalk.dll").is_file())
+            })
+            .unwrap_or(false);
+        if !has_library {
+            return Err(WineError::LibraryNotFound(self.lib_path.clone()));
+        }
+
+        let output = self
+            .command(&["stdio"])
+            .stdin(StdioEnum::null())
+            .stdout(StdioEnum::null())
+            .stderr(StdioEnum::piped())
+            .output()
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => WineError::WineNotFound,
+                _ => WineError::Io(err),
+            })?;
+        if output.status.success() {
+            return Ok(());
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let wine32_missing = [
+            "wine32 is missing",
+            "Bad EXE format",
+            "could not load kernel32.dll",
+        ]
+        .iter()
+        .any(|message| stderr.contains(message));
+        if wine32_missing {
+            Err(WineError::Wine32Unavailable(stderr))
+        } else {
+            Err(WineError::Startup(stderr))
+        }
+    }
+
+    fn opener(self) -> WineOpener {
+        Box::new(move |command| self.configure(command, &["stdio"]))
+    }
+}
+
+impl StdioClient<PathBuf, WineOpener> {
+    /// Wine で標準入出力モードの `aquestalk-proxyd.exe` を起動する
+    ///
+    /// 起動に失敗する環境ではエラーを返す。子プロセスが終了した場合は次の要求で起動し直す。
+    pub fn wine(wine: Wine) -> Result<Self, WineError> {
+        wine.check()?;
+        Ok(Self::new(wine.wine.clone(), wine.opener()))
+    }
+}
+
+impl StdioClientPool<PathBuf, WineOpener> {
+    /// [`StdioClient::wine`] のプール版
+    pub fn wine(wine: Wine, min: usize, max: usize) -> Result<Self, WineError> {
+        wine.check()?;
+        Ok(Self::new(wine.wine.clone(), wine.opener(), min, max))
+    }
+}
+
+fn find_wine() -> Option<PathBuf> {
+    if let Some(wine) = env::var_os("WINE") {
+        return Some(wine.into());
+    }
+
+    let paths = env::var_os("PATH")?;
+    ["wine", "wine64"].iter().find_map(|name| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    })
+}
+
+/// Wine では `/` が `Z:\` に割り当てられているため、絶対パスを Windows 形式に変換する
+fn windows_path(path: &Path) -> OsString {
+    if cfg!(windows) {
+        return path.into();
+    }
+
+    match path::absolute(path) {
+        Ok(path) => {
+            let mut windows = OsString::from("Z:");
+            windows.push(path.as_os_str().to_string_lossy().replace('/', "\\"));
+            windows
+        }
+        Err(_) => path.into(),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::{Wine, WineError};
+
+    #[test]
+    fn command() {
+        let wine = Wine::with_wine(
+            "/usr/bin/wine",
+            "/opt/aqtk/aquestalk-proxyd.exe",
+            "aquestalk",
+        )
+        .with_prefix("/tmp/prefix");
+        let command = wine.command(&["tcp", "-l", "0.0.0.0:21569"]);
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(command.get_program(), "/usr/bin/wine");
+        assert_eq!(args[0], "Z:\\opt\\aqtk\\aquestalk-proxyd.exe");
+        assert!(args[1].to_str().unwrap().starts_with("--path=Z:\\"));
+        assert!(args[1].to_str().unwrap().ends_with("\\aquestalk"));
+        assert_eq!(args[2..], ["tcp", "-l", "0.0.0.0:21569"]);
+        assert!(command
+            .get_envs()
+            .any(|(key, value)| key == "WINEPREFIX" && value == Some("/tmp/prefix".as_ref())));
+    }
+
+    #[test]
+    fn check() {
+        let dir = env::temp_dir().join(format!("aquestalk-proxy-wine-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("aquestalk/f1")).unwrap();
+
+        // wine の代わりに sh で引数の exe (シェルスクリプト) を実行する
+        let exe = dir.join("aquestalk-proxyd.exe");
+        let wine = |script: &str| {
+            fs::write(&exe, script).unwrap();
+            fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).unwrap();
+            Wine::with_wine(dir.join("wine"), &exe, dir.join("aquestalk"))
+        };
+        fs::write(
+            dir.join("wine"),
+            r#"#!/bin/sh
+exec sh "$(printf '%s' "$1" | sed -e 's/^Z://' -e 's|\\|/|g')"
+"#,
+        )
+        .unwrap();
+        fs::set_permissions(dir.join("wine"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(matches!(
+            wine("exit 0").check(),
+            Err(WineError::LibraryNotFound(_))
+        ));
+        fs::write(dir.join("aquestalk/f1/AquesTalk.dll"), "").unwrap();
+        wine("exit 0").check().unwrap();
+        assert!(matches!(
+            wine("echo 'it looks like wine32 is missing' >&2; exit 1").check(),
+            Err(WineError::Wine32Unavailable(_))
+        ));
+        assert!(matches!(
+            wine("echo 'LoadLibrary failed' >&2; exit 101").check(),
+            Err(WineError::Startup(stderr)) if stderr == "LoadLibrary failed"
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-// Copyright (c) 2022 Na-x4
+// Copyright (c) 2022-2026 Na-x4
 //
 // Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
 // https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
@@ -6,16 +6,39 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::io::BufReader;
+use std::io::{self, BufReader, ErrorKind};
 use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
-use crate::aquestalk::AquesTalk;
-use crate::messages::ResponsePayload;
+use crate::aquestalk::{AquesTalk, SynthesisRequest};
+use crate::messages::Response;
+use crate::ClientError;
 
-type Client<'a> = super::Client<BufReader<&'a TcpStream>, &'a TcpStream>;
+use super::into_wav;
+use super::retry::RetryPolicy;
 
+type Client = super::Client<BufReader<TcpStream>, TcpStream>;
+
+/// 接続を使い回す TCP クライアント
+///
+/// 使用中でない接続はアドレスごとのプールに保持され、スレッド間で共有される。
+/// `willClose` を受け取った接続や通信に失敗した接続は破棄する。
+///
+/// 複数のアドレスを指定した場合は、再試行のたびに次のアドレスに切り替える。
+/// 全てのアドレスを試した後は [`RetryPolicy`] に従って待ってから最初のアドレスに戻る。
+/// 切り替えた後も、次の要求は最初のアドレスから試す。
+///
+/// タイムアウトした場合は [`ClientError::Timeout`] を返し、その接続は破棄する。
 pub struct TcpClient<A> {
-    addr: A,
+    addrs: Vec<A>,
+    retry: RetryPolicy,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    /// `addrs` と同じ順に並べた接続のプール
+    pool: Mutex<Vec<Vec<Client>>>,
 }
 
 impl<A> TcpClient<A>
@@ -23,8 +46,105 @@ where
     A: ToSocketAddrs,
 {
     pub fn new(addr: A) -> Self {
-        Self { addr }
+        Self::with_addrs(vec![addr])
+    }
+
+    /// 優先順に並べたアドレスを指定する
+    pub fn with_addrs(addrs: Vec<A>) -> Self {
+        assert!(!addrs.is_empty(), "no address is specified");
+        let pool = addrs.iter().map(|_| Vec::new()).collect();
+        Self {
+            addrs,
+            retry: RetryPolicy::default(),
+            connect_timeout: None,
+            read_timeout: None,
+            write_timeout: None,
+            pool: Mutex::new(pool),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// 応答を待つ時間の上限
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    fn connect(&self, addr: &A) -> Result<Client, ClientError> {
+        let stream = match self.connect_timeout {
+            Some(timeout) => connect_timeout(addr, timeout),
+            None => TcpStream::connect(addr),
+        }
+        .map_err(ClientError::from)?;
+        stream
+            .set_read_timeout(self.read_timeout)
+            .map_err(ClientError::from)?;
+        stream
+            .set_write_timeout(self.write_timeout)
+            .map_err(ClientError::from)?;
+        let reader = BufReader::new(stream.try_clone().map_err(ClientError::from)?);
+        Ok(Client::new(reader, stream))
+    }
+
+    fn send(
+        &self,
+        index: usize,
+        request: &SynthesisRequest,
+    ) -> Result<(Client, Result<Response, ClientError>), ClientError> {
+        let pooled = self.pool.lock().unwrap()[index].pop();
+        if let Some(mut client) = pooled {
+            match client.send(request) {
+                // サーバー側のタイムアウトなどで閉じられている場合は新しい接続でやり直す。
+                // それ以外のエラーは要求が届いている可能性があるため、再試行は RetryPolicy に任せる
+                Err(ClientError::Transport { .. } | ClientError::Closed) => (),
+                result => return Ok((client, result)),
+            }
+        }
+
+        let mut client = self.connect(&self.addrs[index])?;
+        let result = client.send(request);
+        Ok((client, result))
+    }
+
+    fn try_synthe(&self, attempt: u32, request: &SynthesisRequest) -> Result<Vec<u8>, ClientError> {
+        let index = attempt as usize % self.addrs.len();
+        let (client, result) = self.send(index, request)?;
+        if !client.is_closed() {
+            self.pool.lock().unwrap()[index].push(client);
+        }
+        into_wav(result?, request)
+    }
+}
+
+/// [`TcpStream::connect`] と同様に解決された全てのアドレスを順に試す
+fn connect_timeout<A: ToSocketAddrs>(addr: &A, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_err = None;
+    for addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_err = Some(err),
+        }
     }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            "could not resolve to any addresses",
+        )
+    }))
 }
 
 impl<A> AquesTalk for TcpClient<A>
@@ -32,29 +152,247 @@ where
     A: ToSocketAddrs,
 {
     type Wav = Vec<u8>;
-    fn synthe(
-        &self,
-        voice_type: &str,
-        koe: &str,
-        speed: i32,
-    ) -> Result<Self::Wav, crate::messages::ResponsePayload> {
-        let stream = TcpStream::connect(&self.addr).map_err(ResponsePayload::from_io_error)?;
-        let mut client = Client::new(BufReader::new(&stream), &stream);
-        client.synthe(voice_type, koe, speed)
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        let mut attempt = 0;
+        loop {
+            match self.try_synthe(attempt, request) {
+                Err(err)
+                    if attempt + 1 < self.retry.max_attempts && self.retry.is_retryable(&err) =>
+                {
+                    attempt += 1;
+                    // 全てのアドレスを試し終わるまでは待たずに次のアドレスを試す
+                    // (`is_multiple_of` は Rust 1.87 以降のため使わない)
+                    #[allow(clippy::manual_is_multiple_of)]
+                    if attempt as usize % self.addrs.len() == 0 {
+                        let round = attempt / self.addrs.len() as u32;
+                        thread::sleep(self.retry.backoff(round - 1));
+                    }
+                }
+                result => return result,
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use std::env;
+    use std::io::Write;
+    use std::net::{SocketAddr, TcpListener};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use serde_json::{Deserializer, Value};
 
     use crate::aquestalk::AquesTalk;
+    use crate::proxy::retry::RetryPolicy;
+    use crate::ClientError;
     use crate::TcpClient;
 
+    /// 要求ごとに `respond` の返す応答 (空の場合は送らない) を送り、受け付けた接続数と要求数を数えるサーバー
+    fn serve<F>(respond: F) -> (SocketAddr, Arc<AtomicUsize>, Arc<AtomicUsize>)
+    where
+        F: Fn(&str) -> (&'static str, bool) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let (connection_count, request_count) = (Arc::clone(&connections), Arc::clone(&requests));
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = stream.unwrap();
+                connection_count.fetch_add(1, Ordering::SeqCst);
+                for request in Deserializer::from_reader(&stream).into_iter::<Value>() {
+                    request_count.fetch_add(1, Ordering::SeqCst);
+                    let (response, close) = respond(request.unwrap()["koe"].as_str().unwrap());
+                    if !response.is_empty() {
+                        writeln!(&stream, "{}", response).unwrap();
+                    }
+                    if close {
+                        break;
+                    }
+                }
+            }
+        });
+
+        (addr, connections, requests)
+    }
+
+    fn closed_addr() -> SocketAddr {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    const WAV: &str = r#"{"isSuccess":true,"response":{"type":"Wav","wav":"UklGRg=="}}"#;
+
     #[test]
     fn tcp() {
         let aqtk = TcpClient::new(env::var("AQTK_PROXY").unwrap_or("localhost:21569".into()));
         aqtk.synthe("f1", "こんにちわ、せ'かい", 100).unwrap();
         aqtk.synthe("f1", "ゆっくりしていってね", 100).unwrap();
     }
+
+    #[test]
+    fn pool() {
+        // "bye" には willClose を返し、"idle" には応答した後に何も言わずに切断する
+        let (addr, connections, _) = serve(|koe| match koe {
+            "bye" => (
+                r#"{"isSuccess":true,"response":{"type":"Wav","wav":"UklGRg=="},"willClose":true}"#,
+                true,
+            ),
+            "idle" => (WAV, true),
+            _ => (WAV, false),
+        });
+
+        let aqtk = TcpClient::new(addr);
+        for (koe, expected) in [
+            ("a", 1),
+            ("b", 1),
+            ("bye", 1),
+            ("c", 2),
+            ("idle", 2),
+            ("d", 3),
+        ] {
+            assert_eq!(aqtk.synthe("f1", koe, 100).unwrap(), b"RIFF");
+            assert_eq!(connections.load(Ordering::SeqCst), expected, "{}", koe);
+        }
+    }
+
+    #[test]
+    fn failover() {
+        let (addr, _, _) = serve(|_| (WAV, false));
+
+        let aqtk = TcpClient::with_addrs(vec![closed_addr(), addr]);
+        assert_eq!(aqtk.synthe("f1", "あ", 100).unwrap(), b"RIFF");
+
+        let aqtk =
+            TcpClient::with_addrs(vec![closed_addr(), addr]).with_retry(RetryPolicy::never());
+        assert!(aqtk.synthe("f1", "あ", 100).is_err());
+
+        // 切り替えた後も次の要求は最初のアドレスに送る
+        let (primary, _, primary_requests) = serve(|koe| match koe {
+            // 応答せずに切断する
+            "down" => ("", true),
+            _ => (WAV, false),
+        });
+        let (backup, _, backup_requests) = serve(|_| (WAV, false));
+        let aqtk = TcpClient::with_addrs(vec![primary, backup]);
+        assert_eq!(aqtk.synthe("f1", "down", 100).unwrap(), b"RIFF");
+        assert_eq!(aqtk.synthe("f1", "あ", 100).unwrap(), b"RIFF");
+        assert_eq!(primary_requests.load(Ordering::SeqCst), 2);
+        assert_eq!(backup_requests.load(Ordering::SeqCst), 1);
+
+        let aqtk = TcpClient::new(closed_addr()).with_retry(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        });
+        assert!(aqtk.synthe("f1", "あ", 100).is_err());
+    }
+
+    #[test]
+    fn read_timeout() {
+        // 接続を受け付けるが応答しないサーバー
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut streams = Vec::new();
+            for stream in listener.incoming() {
+                streams.push(stream.unwrap());
+            }
+        });
+
+        let aqtk = TcpClient::new(addr)
+            .with_read_timeout(Duration::from_millis(100))
+            .with_retry(RetryPolicy::never());
+        for _ in 0..2 {
+            assert!(matches!(
+                aqtk.synthe("f1", "あ", 100),
+                Err(ClientError::Timeout { .. })
+            ));
+        }
+        assert!(aqtk.pool.lock().unwrap()[0].is_empty());
+    }
+
+    #[test]
+    fn no_resend_on_timeout() {
+        // "slow" には応答が遅れるサーバー
+        let (addr, _, requests) = serve(|koe| {
+            if koe == "slow" {
+                thread::sleep(Duration::from_millis(300));
+            }
+            (WAV, false)
+        });
+
+        let aqtk = TcpClient::new(addr)
+            .with_read_timeout(Duration::from_millis(100))
+            .with_retry(RetryPolicy::never());
+        assert_eq!(aqtk.synthe("f1", "a", 100).unwrap(), b"RIFF");
+        assert!(matches!(
+            aqtk.synthe("f1", "slow", 100),
+            Err(ClientError::Timeout { .. })
+        ));
+
+        // プールした接続でタイムアウトしても新しい接続で送り直さない
+        thread::sleep(Duration::from_millis(500));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn no_retry_on_aquestalk_error() {
+        let (addr, _, requests) = serve(|_| {
+            (
+                r#"{"isSuccess":false,"response":{"type":"AquestalkError","code":105,"message":"x"}}"#,
+                false,
+            )
+        });
+
+        let aqtk = TcpClient::new(addr);
+        assert!(aqtk.synthe("f1", "あ", 100).is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn no_retry_on_server_error() {
+        let (addr, _, requests) = serve(|_| {
+            (
+                r#"{"isSuccess":false,"response":{"type":"IoError","message":"Request is too long"}}"#,
+                false,
+            )
+        });
+
+        // サーバーが返した `IoError` は別のアドレスでも同じ結果になる
+        let aqtk = TcpClient::with_addrs(vec![addr, addr]);
+        assert_eq!(
+            aqtk.synthe("f1", "あ", 100),
+            Err(ClientError::Server {
+                message: "Request is too long".to_string()
+            })
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn invalid_speed() {
+        let (addr, _, requests) = serve(|_| {
+            (
+                r#"{"isSuccess":false,"response":{"type":"JsonError","message":"x"}}"#,
+                false,
+            )
+        });
+
+        // 発話速度はサーバーが検証し、拒否された場合は `InvalidSpeed` になる
+        let aqtk = TcpClient::new(addr);
+        assert_eq!(
+            aqtk.synthe("f1", "あ", 400),
+            Err(ClientError::InvalidSpeed { speed: 400 })
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
 }
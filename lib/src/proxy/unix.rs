@@ -0,0 +1,120 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::io::BufReader;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::aquestalk::{AquesTalk, SynthesisRequest};
+use crate::ClientError;
+#[cfg(windows)]
+use uds_windows::UnixStream;
+
+type Client<'a> = super::Client<BufReader<&'a UnixStream>, &'a UnixStream>;
+
+/// Unix ドメインソケットで待ち受けしているサーバーのクライアント
+///
+/// Windows では `uds_windows` で `AF_UNIX` ソケットに接続する。
+pub struct UnixClient<P> {
+    path: P,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+}
+
+impl<P> UnixClient<P>
+where
+    P: AsRef<Path>,
+{
+    pub fn new(path: P) -> Self {
+        Self {
+            path,
+            read_timeout: None,
+            write_timeout: None,
+        }
+    }
+
+    /// 応答を待つ時間の上限
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = Some(timeout);
+        self
+    }
+}
+
+impl<P> AquesTalk for UnixClient<P>
+where
+    P: AsRef<Path>,
+{
+    type Wav = Vec<u8>;
+    fn synthe_request(&self, request: &SynthesisRequest) -> Result<Self::Wav, ClientError> {
+        let stream = UnixStream::connect(&self.path).map_err(ClientError::from)?;
+        stream
+            .set_read_timeout(self.read_timeout)
+            .map_err(ClientError::from)?;
+        stream
+            .set_write_timeout(self.write_timeout)
+            .map_err(ClientError::from)?;
+        let mut client = Client::new(BufReader::new(&stream), &stream);
+        client.synthe(request)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+    #[cfg(unix)]
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    use serde_json::{Deserializer, Value};
+    #[cfg(windows)]
+    use uds_windows::UnixListener;
+
+    use crate::aquestalk::AquesTalk;
+    use crate::UnixClient;
+
+    #[test]
+    fn unix() {
+        let path =
+            env::temp_dir().join(format!("aquestalk-proxy-test-{}.sock", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let request = Deserializer::from_reader(&stream)
+                .into_iter::<Value>()
+                .next()
+                .unwrap()
+                .unwrap();
+            assert_eq!(request["koe"], "こんにちわ、せ'かい");
+            (&stream)
+                .write_all(
+                    b"{\"isSuccess\":true,\"response\":{\"type\":\"Wav\",\"wav\":\"UklGRg==\"}}\n",
+                )
+                .unwrap();
+        });
+
+        let aqtk = UnixClient::new(&path);
+        assert_eq!(
+            aqtk.synthe("f1", "こんにちわ、せ'かい", 100).unwrap(),
+            b"RIFF"
+        );
+
+        server.join().unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright (c) 2026 Na-x4
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! AquesTalk-proxy クライアントの Python 拡張モジュール
+//!
+//! `maturin build` で `aquestalk_proxy` モジュールとしてビルドする。
+
+use std::time::Duration;
+
+use aquestalk_proxy::aquestalk::{AquesTalk, Koe};
+use aquestalk_proxy::messages::{Response, ResponsePayload};
+use aquestalk_proxy::ClientError;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyOSError, PyTimeoutError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyBytes, PyDict, PyTuple, PyType};
+
+create_exception!(
+    aquestalk_proxy,
+    Error,
+    PyException,
+    "AquesTalk-proxy のエラーの基底クラス"
+);
+create_exception!(
+    aquestalk_proxy,
+    AquestalkError,
+    Error,
+    "AquesTalk ライブラリ内エラー (`code` にエラーコード)"
+);
+create_exception!(aquestalk_proxy, JsonError, Error, "JSON のエラー");
+create_exception!(
+    aquestalk_proxy,
+    InvalidSpeedError,
+    Error,
+    "発話速度が範囲外 (`speed` に発話速度)"
+);
+
+static IO_ERROR: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+static TIMEOUT_ERROR: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+
+/// `bases` を全て継承した例外クラスを作る
+///
+/// `create_exception!` では基底クラスを 1 つしか指定できないため、`type()` を呼び出して作る。
+fn new_exception<'py>(
+    py: Python<'py>,
+    name: &str,
+    bases: &[Bound<'py, PyType>],
+    doc: &str,
+) -> PyResult<Py<PyType>> {
+    let dict = PyDict::new(py);
+    dict.set_item("__module__", "aquestalk_proxy")?;
+    dict.set_item("__doc__", doc)?;
+    let ty = py
+        .get_type::<PyType>()
+        .call1((name, PyTuple::new(py, bases)?, dict))?;
+    Ok(ty.cast_into::<PyType>()?.unbind())
+}
+
+/// 入出力のエラー (`Error` と組み込みの `OSError` を継承する)
+fn io_error(py: Python<'_>) -> PyResult<&Bound<'_, PyType>> {
+    IO_ERROR
+        .get_or_try_init(py, || {
+            new_exception(
+                py,
+                "IoError",
+                &[py.get_type::<Error>(), py.get_type::<PyOSError>()],
+                "入出力のエラー",
+            )
+        })
+        .map(|ty| ty.bind(py))
+}
+
+/// 応答を待ちきれなかった (`IoError` と組み込みの `TimeoutError` を継承する)
+fn timeout_error(py: Python<'_>) -> PyResult<&Bound<'_, PyType>> {
+    TIMEOUT_ERROR
+        .get_or_try_init(py, || {
+            new_exception(
+                py,
+                "TimeoutError",
+                &[io_error(py)?.clone(), py.get_type::<PyTimeoutError>()],
+                "応答を待ちきれなかった",
+            )
+        })
+        .map(|ty| ty.bind(py))
+}
+
+/// `ty` の例外を作る (`ty` を作れなかった場合はそのエラーを返す)
+fn new_err(ty: PyResult<&Bound<'_, PyType>>, message: String) -> PyErr {
+    match ty {
+        Ok(ty) => PyErr::from_type(ty.clone(), message),
+        Err(err) => err,
+    }
+}
+
+/// `ClientError` を `ResponsePayload` の種類に対応する例外に変換する
+///
+/// `Timeout` は `IoError` の派生の `TimeoutError` にする。
+fn to_py_err(py: Python<'_>, err: ClientError) -> PyErr {
+    if let ClientError::Timeout { message } = err {
+        return new_err(timeout_error(py), message);
+    }
+    let speed = match err {
+        ClientError::InvalidSpeed { speed } => Some(speed),
+        _ => None,
+    };
+    match ResponsePayload::from(err) {
+        ResponsePayload::AquestalkError { code, message } => {
+            let err = AquestalkError::new_err(message);
+            if let Err(err) = err.value(py).setattr("code", code) {
+                return err;
+            }
+            err
+        }
+        ResponsePayload::JsonError { message } => match speed {
+            Some(speed) => {
+                let err = InvalidSpeedError::new_err(message);
+                if let Err(err) = err.value(py).setattr("speed", speed) {
+                    return err;
+                }
+                err
+            }
+            None => JsonError::new_err(message),
+        },
+        ResponsePayload::IoError { message } => new_err(io_error(py), message),
+        ResponsePayload::Wav { .. } => unreachable!(),
+    }
+}
+
+fn to_duration(timeout: Option<f64>) -> PyResult<Option<Duration>> {
+    timeout
+        .map(|timeout| {
+            Duration::try_from_secs_f64(timeout)
+                .map_err(|err| PyValueError::new_err(format!("timeout: {}", err)))
+        })
+        .transpose()
+}
+
+type Inner = Box<dyn AquesTalk<Wav = Vec<u8>> + Send + Sync>;
+
+/// 合成中は GIL を解放する
+fn synthe<'py>(
+    py: Python<'py>,
+    inner: &Inner,
+    koe: &str,
+    voice_type: &str,
+    speed: i32,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let wav = py
+        .detach(|| inner.synthe(voice_type, koe, speed))
+        .map_err(|err| to_py_err(py, err))?;
+    Ok(PyBytes::new(py, &wav))
+}
+
+/// TCP モードのデーモンに接続するクライアント
+///
+/// `timeout` は接続、送信、受信それぞれの待ち時間の上限 (秒)。
+#[pyclass(frozen, module = "aquestalk_proxy")]
+struct TcpClient(Inner);
+
+#[pymethods]
+impl TcpClient {
+    #[new]
+    #[pyo3(signature = (addr = "localhost:21569".to_string(), timeout = None))]
+    fn new(addr: String, timeout: Option<f64>) -> PyResult<Self> {
+        let mut client = aquestalk_proxy::TcpClient::new(addr);
+        if let Some(timeout) = to_duration(timeout)? {
+            client = client
+                .with_connect_timeout(timeout)
+                .with_read_timeout(timeout)
+                .with_write_timeout(timeout);
+        }
+        Ok(Self(Box::new(client)))
+    }
+
+    /// 音声記号列を WAV データに変換する
+    #[pyo3(signature = (koe, voice_type = "f1", speed = 100))]
+    fn synthe<'py>(
+        &self,
+        py: Python<'py>,
+        koe: &str,
+        voice_type: &str,
+        speed: i32,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        synthe(py, &self.0, koe, voice_type, speed)
+    }
+}
+
+/// 標準入出力モードのデーモンを子プロセスとして起動するクライアント
+///
+/// `timeout` は 1 回の要求で応答を待つ時間の上限 (秒)。
+#[pyclass(frozen, module = "aquestalk_proxy")]
+struct StdioClient(Inner);
+
+#[pymethods]
+impl StdioClient {
+    #[new]
+    #[pyo3(signature = (program, args = Vec::new(), timeout = None))]
+    fn new(program: String, args: Vec<String>, timeout: Option<f64>) -> PyResult<Self> {
+        let mut client =
+            aquestalk_proxy::StdioClient::new(program, move |command| command.args(&args));
+        if let Some(timeout) = to_duration(timeout)? {
+            client = client.with_timeout(timeout);
+        }
+        Ok(Self(Box::new(client)))
+    }
+
+    /// 音声記号列を WAV データに変換する
+    #[pyo3(signature = (koe, voice_type = "f1", speed = 100))]
+    fn synthe<'py>(
+        &self,
+        py: Python<'py>,
+        koe: &str,
+        voice_type: &str,
+        speed: i32,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        synthe(py, &self.0, koe, voice_type, speed)
+    }
+}
+
+/// 音声記号列を AquesTalk に渡せるか確かめ、不正な場合は `AquestalkError` を送出する
+#[pyfunction]
+fn validate_koe(py: Python<'_>, koe: &str) -> PyResult<()> {
+    koe.parse::<Koe>()
+        .map(|_| ())
+        .map_err(|err| to_py_err(py, err.into()))
+}
+
+/// デーモンの応答 (`Response` の JSON) から WAV データを取り出す
+///
+/// エラーの応答の場合は対応する例外を送出する。
+#[pyfunction]
+fn decode_response<'py>(py: Python<'py>, response: &str) -> PyResult<Bound<'py, PyBytes>> {
+    let response: Response =
+        serde_json::from_str(response).map_err(|err| to_py_err(py, err.into()))?;
+    let wav: Vec<u8> = response
+        .response
+        .try_into()
+        .map_err(|err: ResponsePayload| to_py_err(py, err.into()))?;
+    Ok(PyBytes::new(py, &wav))
+}
+
+#[pymodule]
+#[pyo3(name = "aquestalk_proxy")]
+fn aquestalk_proxy_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("Error", py.get_type::<Error>())?;
+    m.add("AquestalkError", py.get_type::<AquestalkError>())?;
+    m.add("JsonError", py.get_type::<JsonError>())?;
+    m.add("InvalidSpeedError", py.get_type::<InvalidSpeedError>())?;
+    m.add("IoError", io_error(py)?)?;
+    m.add("TimeoutError", timeout_error(py)?)?;
+    m.add_class::<TcpClient>()?;
+    m.add_class::<StdioClient>()?;
+    m.add_function(wrap_pyfunction!(validate_koe, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_response, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use pyo3::exceptions::{PyOSError, PyTimeoutError};
+    use pyo3::prelude::*;
+
+    use aquestalk_proxy::ClientError;
+
+    use super::{
+        decode_response, io_error, to_py_err, AquestalkError, Error, InvalidSpeedError, JsonError,
+    };
+
+    #[test]
+    fn decode() {
+        Python::initialize();
+        Python::attach(|py| {
+            let wav = decode_response(
+                py,
+                r#"{"isSuccess":true,"response":{"type":"Wav","wav":"UklGRg=="}}"#,
+            )
+            .unwrap();
+            assert_eq!(wav.as_bytes(), b"RIFF");
+
+            let err = decode_response(
+                py,
+                r#"{"isSuccess":false,"response":{"type":"AquestalkError","code":105,"message":"x"}}"#,
+            )
+            .unwrap_err();
+            assert!(err.is_instance_of::<AquestalkError>(py));
+            let code: i32 = err.value(py).getattr("code").unwrap().extract().unwrap();
+            assert_eq!(code, 105);
+
+            let err = decode_response(py, "{").unwrap_err();
+            assert!(err.is_instance_of::<JsonError>(py));
+        });
+    }
+
+    #[test]
+    fn invalid_speed() {
+        Python::initialize();
+        Python::attach(|py| {
+            let err = to_py_err(py, ClientError::InvalidSpeed { speed: 400 });
+            assert!(err.is_instance_of::<InvalidSpeedError>(py));
+            assert!(err.is_instance_of::<Error>(py));
+            assert!(!err.is_instance_of::<JsonError>(py));
+            let speed: i32 = err.value(py).getattr("speed").unwrap().extract().unwrap();
+            assert_eq!(speed, 400);
+        });
+    }
+
+    #[test]
+    fn builtin_bases() {
+        Python::initialize();
+        Python::attach(|py| {
+            let err = to_py_err(
+                py,
+                ClientError::Timeout {
+                    message: "x".to_string(),
+                },
+            );
+            assert!(err.is_instance_of::<PyTimeoutError>(py));
+            assert!(err.is_instance(py, io_error(py).unwrap()));
+            assert!(err.is_instance_of::<Error>(py));
+
+            let err = to_py_err(
+                py,
+                ClientError::Transport {
+                    message: "x".to_string(),
+                },
+            );
+            assert!(err.is_instance_of::<PyOSError>(py));
+            assert!(!err.is_instance_of::<PyTimeoutError>(py));
+        });
+    }
+}
@@ -0,0 +1,270 @@
+// AquesTalk-proxy - Copyright (C) 2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use aquestalk_proxy::Wine;
+use getopts::{Options, ParsingStyle};
+
+/// これより長く動いた後に終了した場合は連続した再起動に数えない
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
+/// `--shutdown-timeout` までに処理中の要求が完了せずに終了した場合の終了コード
+const EXIT_SHUTDOWN_TIMEOUT: i32 = 2;
+
+struct SupervisorOptions {
+    wine: Wine,
+    mode: Vec<String>,
+    restart_delay: Duration,
+    max_restarts: Option<u32>,
+}
+
+fn format_usage(program: &str, opts: Options) -> String {
+    format!(
+        "\
+AquesTalk-proxy Wine Supervisor
+
+Runs aquestalk-proxyd.exe under Wine and restarts it when it dies.
+MODE and MODE OPTIONS are passed to aquestalk-proxyd.exe.
+
+USAGE:
+    {} [OPTIONS] [MODE [MODE OPTIONS]]
+
+OPTIONS:
+{}
+",
+        program,
+        opts.usage_with_format(|opts| { opts.collect::<Vec<String>>().join("\n") })
+    )
+}
+
+fn parse_options(args: Vec<String>) -> Result<SupervisorOptions, i32> {
+    let program = args[0].clone();
+
+    let mut opts = Options::new();
+    opts.parsing_style(ParsingStyle::StopAtFirstFree);
+    opts.optopt(
+        "",
+        "exe",
+        "Path to aquestalk-proxyd.exe (Default: ./aquestalk-proxyd.exe)",
+        "PATH",
+    );
+    opts.optopt(
+        "p",
+        "path",
+        "Path to AquesTalk library (Default: ./aquestalk)",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "wine",
+        "Path to wine (Default: $WINE or wine in PATH)",
+        "PATH",
+    );
+    opts.optopt("", "prefix", "WINEPREFIX (Default: $WINEPREFIX)", "PATH");
+    opts.optopt(
+        "",
+        "restart-delay",
+        "Delay before restarting in milliseconds (Default: 1000)",
+        "MILLIS",
+    );
+    opts.optopt(
+        "",
+        "max-restarts",
+        "Max consecutive restarts of a daemon exiting within a minute",
+        "NUM",
+    );
+    opts.optflag("h", "help", "Print help");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("{}\nERROR: {}", format_usage(&program, opts), f);
+            return Err(1);
+        }
+    };
+
+    if matches.opt_present("h") {
+        println!("{}", format_usage(&program, opts));
+        return Err(0);
+    }
+
+    let exe = matches
+        .opt_str("exe")
+        .map_or_else(|| PathBuf::from("aquestalk-proxyd.exe"), PathBuf::from);
+    let lib_path = matches
+        .opt_str("p")
+        .map_or_else(|| PathBuf::from("aquestalk"), PathBuf::from);
+    let restart_delay = match matches.opt_get_default("restart-delay", 1000) {
+        Ok(delay) => delay,
+        Err(err) => {
+            eprintln!(
+                "{}\nERROR: Invalid restart delay: {}",
+                format_usage(&program, opts),
+                err
+            );
+            return Err(1);
+        }
+    };
+    let max_restarts = match matches.opt_get("max-restarts") {
+        Ok(max) => max,
+        Err(err) => {
+            eprintln!(
+                "{}\nERROR: Invalid max restarts: {}",
+                format_usage(&program, opts),
+                err
+            );
+            return Err(1);
+        }
+    };
+
+    let wine = match matches.opt_str("wine") {
+        Some(wine) => Wine::with_wine(wine, exe, lib_path),
+        None => match Wine::new(exe, lib_path) {
+            Ok(wine) => wine,
+            Err(err) => {
+                eprintln!("ERROR: {}", err);
+                return Err(1);
+            }
+        },
+    };
+    let wine = match matches.opt_str("prefix") {
+        Some(prefix) => wine.with_prefix(prefix),
+        None => wine,
+    };
+
+    Ok(SupervisorOptions {
+        wine,
+        mode: matches.free,
+        restart_delay: Duration::from_millis(restart_delay),
+        max_restarts,
+    })
+}
+
+fn main() {
+    exit(run());
+}
+
+/// 受け取ったシグナルを子プロセスに伝える
+///
+/// Wine は SIGINT を Windows プログラムへの Ctrl+C として伝えるため、
+/// SIGTERM を受け取った場合も SIGINT を送ってデーモンの終了処理を行わせる。
+#[cfg(unix)]
+fn forward_signal(pid: u32) {
+    unsafe {
+        libc::kill(pid as libc::pid_t, libc::SIGINT);
+    }
+}
+
+/// Windows ではコンソールの Ctrl+C が子プロセスにも届く
+#[cfg(not(unix))]
+fn forward_signal(_pid: u32) {}
+
+fn run() -> i32 {
+    let options = match parse_options(env::args().collect()) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+
+    if let Err(err) = options.wine.check() {
+        eprintln!("ERROR: {}", err);
+        return 1;
+    }
+
+    // 終了を要求された後は再起動しない
+    let stopping = Arc::new(AtomicBool::new(false));
+    let child_pid = Arc::new(Mutex::new(None));
+    let (stop_tx, stop_rx) = mpsc::channel();
+    {
+        let (stopping, child_pid) = (Arc::clone(&stopping), Arc::clone(&child_pid));
+        let result = ctrlc::set_handler(move || {
+            stopping.store(true, Ordering::SeqCst);
+            if let Some(pid) = *child_pid.lock().unwrap() {
+                forward_signal(pid);
+            }
+            let _ = stop_tx.send(());
+        });
+        if let Err(err) = result {
+            eprintln!("ERROR: Failed to set signal handler: {}", err);
+            return 1;
+        }
+    }
+
+    let mut restarts = 0;
+    loop {
+        let started = Instant::now();
+        let mut child = {
+            // 起動と PID の記録の間にシグナルを受け取っても取りこぼさないようにする
+            let mut child_pid = child_pid.lock().unwrap();
+            if stopping.load(Ordering::SeqCst) {
+                return 0;
+            }
+            match options.wine.command(&options.mode).spawn() {
+                Ok(child) => {
+                    *child_pid = Some(child.id());
+                    child
+                }
+                Err(err) => {
+                    eprintln!("ERROR: Failed to run wine: {}", err);
+                    return 1;
+                }
+            }
+        };
+        let status = child.wait();
+        *child_pid.lock().unwrap() = None;
+        let status = match status {
+            Ok(status) => status,
+            Err(err) => {
+                eprintln!("ERROR: Failed to wait for wine: {}", err);
+                return 1;
+            }
+        };
+        if status.success() {
+            return 0;
+        }
+        // 終了処理が期限を過ぎた場合や、終了を要求された場合は再起動しない
+        if status.code() == Some(EXIT_SHUTDOWN_TIMEOUT) || stopping.load(Ordering::SeqCst) {
+            eprintln!("aquestalk-proxyd exited ({})", status);
+            return status.code().unwrap_or(1);
+        }
+
+        if started.elapsed() >= STABLE_UPTIME {
+            restarts = 0;
+        }
+        if options.max_restarts.is_some_and(|max| restarts >= max) {
+            eprintln!("ERROR: aquestalk-proxyd exited ({})", status);
+            return status.code().unwrap_or(1);
+        }
+        restarts += 1;
+
+        eprintln!(
+            "aquestalk-proxyd exited ({}). Restarting in {} ms...",
+            status,
+            options.restart_delay.as_millis()
+        );
+        match stop_rx.recv_timeout(options.restart_delay) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => return 0,
+            Err(RecvTimeoutError::Timeout) => (),
+        }
+    }
+}
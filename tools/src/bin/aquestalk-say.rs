@@ -0,0 +1,300 @@
+// AquesTalk-proxy - Copyright (C) 2026 Na-x4
+//
+// This file is part of AquesTalk-proxy.
+//
+// AquesTalk-proxy is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// AquesTalk-proxy is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with AquesTalk-proxy.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::env;
+use std::fs;
+use std::io::{self, stdin, stdout, Read, Write};
+use std::process::exit;
+
+use aquestalk_proxy::aquestalk::AquesTalk;
+use aquestalk_proxy::{StdioClient, TcpClient, Wine};
+use getopts::{Matches, Options};
+use serde_json::Value;
+
+type Backend = Box<dyn AquesTalk<Wav = Vec<u8>>>;
+
+struct Line {
+    voice_type: String,
+    speed: i32,
+    koe: String,
+}
+
+struct SayOptions {
+    backend: Backend,
+    lines: Vec<Line>,
+    output: String,
+}
+
+fn format_usage(program: &str, opts: Options) -> String {
+    format!(
+        "\
+AquesTalk-proxy Command-line Client
+
+USAGE:
+    {0} [OPTIONS] [KOE]
+    {0} [OPTIONS] --batch FILE
+
+KOE is read from --file or standard input if it is not given.
+In batch mode, each non-empty line of FILE is synthesized into a numbered file.
+Lines are JSON objects ({{\"koe\":...,\"type\":...,\"speed\":...}}),
+or \"TYPE,SPEED,KOE\" if FILE ends with .csv (TYPE and SPEED may be empty).
+
+OPTIONS:
+{1}
+",
+        program,
+        opts.usage_with_format(|opts| { opts.collect::<Vec<String>>().join("\n") })
+    )
+}
+
+fn parse_options(args: Vec<String>) -> Result<SayOptions, i32> {
+    let program = args[0].clone();
+
+    let mut opts = Options::new();
+    opts.optopt(
+        "c",
+        "connect",
+        "Address and port of a daemon in TCP mode (Default: localhost:21569)",
+        "ADDR",
+    );
+    opts.optopt(
+        "",
+        "exec",
+        "Spawn a daemon in Standard IO mode instead of connecting",
+        "PROGRAM",
+    );
+    opts.optmulti("", "arg", "Argument for --exec (multiple allowed)", "ARG");
+    opts.optopt(
+        "",
+        "wine",
+        "Spawn aquestalk-proxyd.exe under Wine instead of connecting",
+        "EXE",
+    );
+    opts.optopt(
+        "p",
+        "path",
+        "Path to AquesTalk library for --wine (Default: ./aquestalk)",
+        "PATH",
+    );
+    opts.optopt("", "voice", "Voice type (Default: f1)", "TYPE");
+    opts.optopt("", "speed", "Speed in 50-300 (Default: 100)", "SPEED");
+    opts.optopt("f", "file", "Read KOE from file", "FILE");
+    opts.optopt("", "batch", "Synthesize each line of JSONL or CSV", "FILE");
+    opts.optopt(
+        "o",
+        "output",
+        "WAV file to write, \"-\" for standard output. \"{}\" is replaced with the sequence number and required in batch mode (Default: output.wav, {}.wav in batch mode)",
+        "PATH",
+    );
+    opts.optflag("h", "help", "Print help");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("{}\nERROR: {}", format_usage(&program, opts), f);
+            return Err(1);
+        }
+    };
+
+    if matches.opt_present("h") {
+        println!("{}", format_usage(&program, opts));
+        return Err(0);
+    }
+
+    let voice_type = matches.opt_str("voice").unwrap_or("f1".to_string());
+    let speed = match matches.opt_get_default("speed", 100) {
+        Ok(speed) => speed,
+        Err(err) => {
+            eprintln!("{}\nERROR: --speed: {}", format_usage(&program, opts), err);
+            return Err(1);
+        }
+    };
+
+    let lines = match read_lines(&matches, &voice_type, speed) {
+        Ok(lines) => lines,
+        Err(err) => {
+            eprintln!("ERROR: {}", err);
+            return Err(1);
+        }
+    };
+    let output = matches.opt_str("o").unwrap_or_else(|| {
+        if matches.opt_present("batch") {
+            "{}.wav".to_string()
+        } else {
+            "output.wav".to_string()
+        }
+    });
+    // 全ての行が同じファイルに上書きされないよう、バッチモードでは連番の置き換えを必須にする
+    if matches.opt_present("batch") && !output.contains("{}") {
+        eprintln!(
+            "{}\nERROR: --output must contain \"{{}}\" in batch mode",
+            format_usage(&program, opts)
+        );
+        return Err(1);
+    }
+
+    let backend = match open_backend(&matches) {
+        Ok(backend) => backend,
+        Err(err) => {
+            eprintln!("ERROR: {}", err);
+            return Err(1);
+        }
+    };
+
+    Ok(SayOptions {
+        backend,
+        lines,
+        output,
+    })
+}
+
+fn read_lines(matches: &Matches, voice_type: &str, speed: i32) -> Result<Vec<Line>, String> {
+    if let Some(path) = matches.opt_str("batch") {
+        let text = read_text(&path).map_err(|err| format!("{}: {}", path, err))?;
+        return text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let line = if path.ends_with(".csv") {
+                    parse_csv_line(line, voice_type, speed)
+                } else {
+                    parse_json_line(line, voice_type, speed)
+                };
+                line.map_err(|err| format!("{}:{}: {}", path, i + 1, err))
+            })
+            .collect();
+    }
+
+    let koe = if !matches.free.is_empty() {
+        matches.free.join(" ")
+    } else {
+        let path = matches.opt_str("f").unwrap_or("-".to_string());
+        read_text(&path).map_err(|err| format!("{}: {}", path, err))?
+    };
+    Ok(vec![Line {
+        voice_type: voice_type.to_string(),
+        speed,
+        koe: koe.trim_end_matches(['\r', '\n']).to_string(),
+    }])
+}
+
+fn read_text(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut text = String::new();
+        stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        fs::read_to_string(path)
+    }
+}
+
+fn parse_json_line(line: &str, voice_type: &str, speed: i32) -> Result<Line, String> {
+    let value: Value = serde_json::from_str(line).map_err(|err| err.to_string())?;
+    let koe = value["koe"].as_str().ok_or("\"koe\" is required")?;
+    Ok(Line {
+        voice_type: value["type"].as_str().unwrap_or(voice_type).to_string(),
+        speed: match &value["speed"] {
+            Value::Null => speed,
+            value => value
+                .as_i64()
+                .and_then(|speed| speed.try_into().ok())
+                .ok_or("\"speed\" must be an integer")?,
+        },
+        koe: koe.to_string(),
+    })
+}
+
+fn parse_csv_line(line: &str, voice_type: &str, speed: i32) -> Result<Line, String> {
+    let mut columns = line.splitn(3, ',');
+    let (Some(line_voice_type), Some(line_speed), Some(koe)) =
+        (columns.next(), columns.next(), columns.next())
+    else {
+        return Err("expected TYPE,SPEED,KOE".to_string());
+    };
+    Ok(Line {
+        voice_type: match line_voice_type.trim() {
+            "" => voice_type.to_string(),
+            line_voice_type => line_voice_type.to_string(),
+        },
+        speed: match line_speed.trim() {
+            "" => speed,
+            line_speed => line_speed
+                .parse()
+                .map_err(|err| format!("SPEED: {}", err))?,
+        },
+        koe: koe.to_string(),
+    })
+}
+
+fn open_backend(matches: &Matches) -> Result<Backend, String> {
+    if let Some(exe) = matches.opt_str("wine") {
+        let lib_path = matches.opt_str("p").unwrap_or("aquestalk".to_string());
+        let wine = Wine::new(exe, lib_path).map_err(|err| err.to_string())?;
+        return Ok(Box::new(
+            StdioClient::wine(wine).map_err(|err| err.to_string())?,
+        ));
+    }
+
+    if let Some(program) = matches.opt_str("exec") {
+        let args = matches.opt_strs("arg");
+        return Ok(Box::new(StdioClient::new(program, move |command| {
+            command.args(&args)
+        })));
+    }
+
+    let addr = matches
+        .opt_str("c")
+        .unwrap_or("localhost:21569".to_string());
+    Ok(Box::new(TcpClient::new(addr)))
+}
+
+fn write_wav(path: &str, wav: &[u8]) -> io::Result<()> {
+    if path == "-" {
+        let mut stdout = stdout().lock();
+        stdout.write_all(wav)?;
+        stdout.flush()
+    } else {
+        fs::write(path, wav)
+    }
+}
+
+fn main() {
+    exit(run());
+}
+
+fn run() -> i32 {
+    let options = match parse_options(env::args().collect()) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+    let mut exit_code = 0;
+    for (i, line) in options.lines.iter().enumerate() {
+        let path = options.output.replace("{}", &(i + 1).to_string());
+        let result = options
+            .backend
+            .synthe(&line.voice_type, &line.koe, line.speed)
+            .map_err(|err| err.to_string())
+            .and_then(|wav| write_wav(&path, &wav).map_err(|err| format!("{}: {}", path, err)));
+        if let Err(err) = result {
+            eprintln!("ERROR: {}: {}", line.koe, err);
+            exit_code = 1;
+        }
+    }
+
+    exit_code
+}